@@ -0,0 +1,32 @@
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    float gradient = (1.0 - fragCoord.y / iResolution.y) * 0.4;
+
+    float snow = 0.0;
+
+    for (float k = 0.0; k < 6.0; k += 1.0) {
+        for (float i = 1.0; i < 12.0; i += 1.0) {
+            float cellSize = 2.0 + i * 3.0;
+            float downSpeed = 0.3 + (sin(iTime * 0.4 + k + i * 20.0) + 1.0) * 0.00008;
+
+            vec2 uv = (fragCoord.xy / iResolution.x) + vec2(0.05 * sin((iTime + k * 6185.0) * 0.6 + i) / i, downSpeed * (iTime + k * 1352.0) / i);
+            vec2 uvStep = ceil(uv * cellSize - 0.5) / cellSize;
+
+            float omiVal = fract(sin(dot(uvStep, vec2(32.4691, 94.615))) * 31572.1684);
+
+            if (omiVal < 0.08) {
+                float x = fract(sin(dot(uvStep, vec2(12.9898 + k * 12.0, 78.233 + k * 315.156))) * 43758.5453 + k * 12.0) - 0.5;
+                float y = fract(sin(dot(uvStep, vec2(62.2364 + k * 23.0, 94.674 + k * 95.0))) * 62159.8432 + k * 12.0) - 0.5;
+
+                float randomMagnitude1 = sin(iTime * 2.5) * 0.7 / cellSize;
+                float randomMagnitude2 = cos(iTime * 2.5) * 0.7 / cellSize;
+
+                float d = 5.0 * distance(uvStep + vec2(x * sin(y), y) * randomMagnitude1 + vec2(y, x) * randomMagnitude2, uv.xy);
+
+                float newd = (x + 1.0) * 0.4 * clamp(1.9 - d * (15.0 + (x * 6.3)) * (cellSize / 1.4), 0.0, 1.0);
+                snow += newd;
+            }
+        }
+    }
+
+    fragColor = vec4(snow, snow, snow, 1.0) + gradient * vec4(0.4, 0.8, 1.0, 0.0);
+}
@@ -1,17 +1,29 @@
 struct Uniforms {
-    time: f32,
     resolution: vec2<f32>,
+    time: f32,
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+    aspect: f32,
+    pixel_ratio: f32,
+    mouse: vec4<f32>,
+    date: vec4<f32>,
+    channel_resolution: array<vec4<f32>, 4>,
+    // Where the tile being rendered starts with `--tile`.
+    offset: vec2<f32>,
 };
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
 @fragment
-fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pos = position.xy + uniforms.offset;
+
     var snow = 0.0;
 
     let t = uniforms.time;
-    let uv = pos.xy / uniforms.resolution.x ;
+    let uv = pos / uniforms.resolution.x ;
     let gradient = pos.y / uniforms.resolution.y + 0.3;
 
     let c = cos(t * 2.5) * 0.3;
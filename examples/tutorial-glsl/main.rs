@@ -0,0 +1,7 @@
+use clap::Parser;
+
+fn main() {
+    let args = shader_rs::Options::parse();
+
+    shader_rs::run(shader_rs::glsl(include_str!("shader.frag")), &args);
+}
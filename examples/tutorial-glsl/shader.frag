@@ -0,0 +1,52 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Uniforms {
+    vec2 resolution;
+    float time;
+    float time_delta;
+    int frame;
+    float frame_rate;
+    float aspect;
+    float pixel_ratio;
+    vec4 mouse;
+    vec4 date;
+    vec4 channel_resolution[4];
+    // Where the tile being rendered starts with `--tile`.
+    vec2 offset;
+};
+
+layout(location = 0) out vec4 fragColor;
+
+vec3 palette(float t) {
+    vec3 a = vec3(0.5, 0.5, 0.5);
+    vec3 b = vec3(0.5, 0.5, 0.5);
+    vec3 c = vec3(1.0, 1.0, 1.0);
+    vec3 d = vec3(0.263, 0.416, 0.557);
+
+    return a + b * cos(6.28318 * (c * t + d));
+}
+
+void main() {
+    vec2 pos = gl_FragCoord.xy + offset;
+    vec2 uv0 = (pos * 2.0 - resolution) / resolution.y;
+
+    vec2 uv = uv0;
+    vec3 finalColor = vec3(0.0);
+
+    for (float i = 0.0; i < 4.0; i += 1.0) {
+        uv = fract(uv * 1.5) - 0.5;
+
+        float d = length(uv) * exp(-length(uv0));
+
+        vec3 col = palette(length(uv0) + i * 0.4 + time * 0.4);
+
+        d = sin(d * 8.0 + time) / 8.0;
+        d = abs(d);
+
+        d = pow(0.01 / d, 1.2);
+
+        finalColor += col * d;
+    }
+
+    fragColor = vec4(finalColor, 1.0);
+}
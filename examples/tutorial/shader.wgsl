@@ -1,6 +1,16 @@
 struct Uniforms {
-    time: f32,
     resolution: vec2<f32>,
+    time: f32,
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+    aspect: f32,
+    pixel_ratio: f32,
+    mouse: vec4<f32>,
+    date: vec4<f32>,
+    channel_resolution: array<vec4<f32>, 4>,
+    // Where the tile being rendered starts with `--tile`.
+    offset: vec2<f32>,
 };
 
 @group(0) @binding(0)
@@ -16,8 +26,9 @@ fn palette(t: f32) -> vec3f {
 }
 
 @fragment
-fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
-    let uv0 = (pos.xy * 2.0 - uniforms.resolution) / uniforms.resolution.y;
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pos = position.xy + uniforms.offset;
+    let uv0 = (pos * 2.0 - uniforms.resolution) / uniforms.resolution.y;
 
     var uv = uv0;
     var finalColor = vec3(0.);
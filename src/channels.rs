@@ -0,0 +1,433 @@
+use std::path::PathBuf;
+
+use wgpu::util::DeviceExt;
+
+use crate::{keyboard::Keyboard, ShaderFile};
+
+/// How many buffers can be rendered before the image, like Shadertoy's Buffer A–D.
+pub const MAX_BUFFERS: usize = 4;
+
+/// How many textures are bound to every pass, as `iChannel0` to `iChannel3`.
+pub const CHANNELS: usize = 4;
+
+pub const BUFFER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// What a channel shows, parsed from `buffer-a` to `buffer-d` for buffers, `keyboard` for the
+/// keyboard and any other value as the path of an image file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// The buffer with this index, i.e. the n-th `--buffer`.
+    Buffer(usize),
+    /// A PNG or JPEG image, decoded with the `image` crate.
+    Image(PathBuf),
+    /// Shadertoy's 256×3 keyboard texture: column `k` of rows 0, 1 and 2 tells whether the key
+    /// with JavaScript key code `k` is down, was pressed this frame and is toggled on.
+    Keyboard,
+}
+
+impl std::str::FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "buffer-a" => Self::Buffer(0),
+            "buffer-b" => Self::Buffer(1),
+            "buffer-c" => Self::Buffer(2),
+            "buffer-d" => Self::Buffer(3),
+            "keyboard" => Self::Keyboard,
+            path => Self::Image(path.into()),
+        })
+    }
+}
+
+/// Layout of bind group 1: channel `i` is a filterable texture at binding `2 * i` and its sampler
+/// at binding `2 * i + 1`.
+pub fn create_channel_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let entries: Vec<_> = (0..CHANNELS as u32)
+        .flat_map(|channel| {
+            [
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2 * channel,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2 * channel + 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        })
+        .collect();
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &entries,
+        label: Some("Channel Bind Group Layout"),
+    })
+}
+
+/// An offscreen pass rendering into one of two textures, alternating every frame so it can
+/// sample its own previous frame.
+pub(crate) struct Buffer {
+    pub shader_file: ShaderFile,
+    pub render_pipeline: wgpu::RenderPipeline,
+    /// Bytes of parameters the shader reads.
+    pub params_size: u64,
+
+    textures: [wgpu::Texture; 2],
+    views: [wgpu::TextureView; 2],
+}
+
+impl Buffer {
+    /// The texture written on `frame`, which holds the previous frame's result on `frame + 1`.
+    fn view(&self, frame: usize) -> &wgpu::TextureView {
+        &self.views[frame % 2]
+    }
+}
+
+struct Image {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+/// The buffers rendered before the image each frame, the images loaded from disk and the
+/// keyboard, together with the channel bind groups exposing them to every pass.
+///
+/// Unless assigned otherwise, channel `i` shows buffer `i`. A pass sees this frame's result of
+/// the buffers rendered before it, and the previous frame's result of itself and the buffers
+/// after it; the image sees this frame's result of all of them. Unused channels show a black
+/// texture.
+pub(crate) struct Channels {
+    buffers: Vec<Buffer>,
+    /// Images by channel, when [`Self::assign`] has been called.
+    images: Option<Vec<Option<Image>>>,
+    assignment: Option<Vec<Channel>>,
+    keyboard: Keyboard,
+
+    width: u32,
+    height: u32,
+
+    buffer_sampler: wgpu::Sampler,
+    image_sampler: wgpu::Sampler,
+    /// Reads one key at a time like Shadertoy's, without blending in neighbouring keys or rows.
+    keyboard_sampler: wgpu::Sampler,
+    empty: Image,
+
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// One pair per buffer followed by one for the image, indexed by frame parity.
+    bind_groups: Vec<[wgpu::BindGroup; 2]>,
+}
+
+impl Channels {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) -> Self {
+        let buffer_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Buffer Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let image_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Image Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let keyboard_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Keyboard Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let empty = create_image(device, queue, &image::RgbaImage::new(1, 1));
+
+        let mut channels = Self {
+            buffers: Vec::new(),
+            images: None,
+            assignment: None,
+            keyboard: Keyboard::new(device),
+
+            width,
+            height,
+
+            buffer_sampler,
+            image_sampler,
+            keyboard_sampler,
+            empty,
+
+            bind_group_layout: create_channel_bind_group_layout(device),
+            bind_groups: Vec::new(),
+        };
+
+        channels.create_bind_groups(device);
+
+        channels
+    }
+
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    pub fn buffers(&self) -> impl Iterator<Item = &Buffer> {
+        self.buffers.iter()
+    }
+
+    pub fn buffers_mut(&mut self) -> impl Iterator<Item = &mut Buffer> {
+        self.buffers.iter_mut()
+    }
+
+    pub fn keyboard_mut(&mut self) -> &mut Keyboard {
+        &mut self.keyboard
+    }
+
+    pub fn push_buffer(
+        &mut self,
+        device: &wgpu::Device,
+        shader_file: ShaderFile,
+        render_pipeline: wgpu::RenderPipeline,
+        params_size: u64,
+    ) {
+        assert!(
+            self.buffers.len() < MAX_BUFFERS,
+            "at most {} buffers are supported",
+            MAX_BUFFERS
+        );
+
+        let (textures, views) = create_textures(device, self.width, self.height);
+
+        self.buffers.push(Buffer {
+            shader_file,
+            render_pipeline,
+            params_size,
+
+            textures,
+            views,
+        });
+
+        self.create_bind_groups(device);
+    }
+
+    /// Shows `assignment[i]` in channel `i`, loading images and flipping them vertically if
+    /// `flip_images` is set, for shaders whose texture coordinates start at the bottom.
+    pub fn assign(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        assignment: Vec<Channel>,
+        flip_images: bool,
+    ) -> image::ImageResult<()> {
+        assert!(
+            assignment.len() <= CHANNELS,
+            "at most {} channels are supported",
+            CHANNELS
+        );
+
+        let images = assignment
+            .iter()
+            .map(|channel| match channel {
+                Channel::Image(path) => {
+                    let mut image = image::open(path)?.to_rgba8();
+
+                    if flip_images {
+                        image::imageops::flip_vertical_in_place(&mut image);
+                    }
+
+                    Ok(Some(create_image(device, queue, &image)))
+                }
+                Channel::Buffer(_) | Channel::Keyboard => Ok(None),
+            })
+            .collect::<image::ImageResult<_>>()?;
+
+        self.images = Some(images);
+        self.assignment = Some(assignment);
+
+        self.create_bind_groups(device);
+
+        Ok(())
+    }
+
+    /// Recreates every buffer at the new size, clearing their contents.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        for buffer in &mut self.buffers {
+            (buffer.textures, buffer.views) = create_textures(device, width, height);
+        }
+
+        self.create_bind_groups(device);
+    }
+
+    /// Shadertoy's `iChannelResolution`: the size of every channel in pixels, or zero when empty.
+    pub fn resolutions(&self) -> [[f32; 4]; CHANNELS] {
+        let mut resolutions = [[0.; 4]; CHANNELS];
+
+        for (channel, resolution) in resolutions.iter_mut().enumerate() {
+            let size = match self.channel(channel) {
+                Some(Channel::Buffer(buffer)) if buffer < self.buffers.len() => {
+                    [self.width, self.height]
+                }
+                Some(Channel::Image(_)) => {
+                    let texture = &self.image(channel).texture;
+                    [texture.width(), texture.height()]
+                }
+                Some(Channel::Keyboard) => self.keyboard.size(),
+                _ => continue,
+            };
+
+            *resolution = [size[0] as f32, size[1] as f32, 1., 0.];
+        }
+
+        resolutions
+    }
+
+    /// Renders every buffer for `frame`, in order.
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: usize,
+        uniform_bind_group: &wgpu::BindGroup,
+    ) {
+        for (buffer, bind_groups) in self.buffers.iter().zip(&self.bind_groups) {
+            let mut render_pass = crate::create_render_pass(encoder, buffer.view(frame));
+
+            render_pass.set_pipeline(&buffer.render_pipeline);
+            render_pass.set_bind_group(0, uniform_bind_group, &[]);
+            render_pass.set_bind_group(1, &bind_groups[frame % 2], &[]);
+
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// The channels seen by the image on `frame`.
+    pub fn image_bind_group(&self, frame: usize) -> &wgpu::BindGroup {
+        &self.bind_groups[self.buffers.len()][frame % 2]
+    }
+
+    fn channel(&self, channel: usize) -> Option<Channel> {
+        match &self.assignment {
+            Some(assignment) => assignment.get(channel).cloned(),
+            None => Some(Channel::Buffer(channel)),
+        }
+    }
+
+    fn image(&self, channel: usize) -> &Image {
+        self.images
+            .as_ref()
+            .and_then(|images| images.get(channel)?.as_ref())
+            .unwrap_or(&self.empty)
+    }
+
+    fn create_bind_groups(&mut self, device: &wgpu::Device) {
+        self.bind_groups = (0..=self.buffers.len())
+            .map(|pass| [0, 1].map(|parity| self.create_bind_group(device, pass, parity)))
+            .collect();
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        pass: usize,
+        parity: usize,
+    ) -> wgpu::BindGroup {
+        let resources: Vec<_> = (0..CHANNELS)
+            .map(|channel| match self.channel(channel) {
+                Some(Channel::Buffer(buffer)) => match self.buffers.get(buffer) {
+                    Some(buffer_pass) if buffer < pass => {
+                        (buffer_pass.view(parity), &self.buffer_sampler)
+                    }
+                    Some(buffer_pass) => (buffer_pass.view(parity + 1), &self.buffer_sampler),
+                    None => (&self.empty.view, &self.image_sampler),
+                },
+                Some(Channel::Keyboard) => (self.keyboard.view(), &self.keyboard_sampler),
+                _ => (&self.image(channel).view, &self.image_sampler),
+            })
+            .collect();
+
+        let entries: Vec<_> = resources
+            .iter()
+            .enumerate()
+            .flat_map(|(channel, (view, sampler))| {
+                [
+                    wgpu::BindGroupEntry {
+                        binding: 2 * channel as u32,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2 * channel as u32 + 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ]
+            })
+            .collect();
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &entries,
+            label: Some("Channel Bind Group"),
+        })
+    }
+}
+
+fn create_image(device: &wgpu::Device, queue: &wgpu::Queue, image: &image::RgbaImage) -> Image {
+    let texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            view_formats: &[],
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some("Image"),
+        },
+        image.as_raw(),
+    );
+    let view = texture.create_view(&Default::default());
+
+    Image { texture, view }
+}
+
+fn create_textures(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+) -> ([wgpu::Texture; 2], [wgpu::TextureView; 2]) {
+    let textures = [0, 1].map(|_| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            view_formats: &[],
+            format: BUFFER_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            label: Some("Buffer"),
+        })
+    });
+
+    let views = [0, 1].map(|i| textures[i].create_view(&Default::default()));
+
+    (textures, views)
+}
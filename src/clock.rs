@@ -0,0 +1,199 @@
+use std::{collections::HashSet, time::Instant};
+
+use winit::{
+    event::{ElementState, KeyEvent, Modifiers},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::FRAME_RATE;
+
+const MIN_SPEED: f32 = 1. / 64.;
+const MAX_SPEED: f32 = 64.;
+
+/// Playback time of a window, controlled from the keyboard while Ctrl is held, so that the
+/// keys stay free for shaders reading the keyboard:
+///
+/// - Ctrl+Space pauses and resumes.
+/// - Ctrl+Right and Ctrl+Left pause and step one frame forward or back.
+/// - Ctrl+Up and Ctrl+Down double or halve the speed.
+/// - Ctrl and a digit start typing seconds to jump to, digits and `.` followed by Enter jump
+///   there, Escape cancels.
+/// - Ctrl+Home goes back to the first frame at normal speed.
+pub struct Clock {
+    /// Time at `instant`.
+    time: f32,
+    instant: Instant,
+    frame: i32,
+    /// Frames per second stepped through, and counted when jumping.
+    frame_rate: f32,
+
+    speed: f32,
+    paused: bool,
+
+    /// Seconds typed so far to jump to.
+    seek: Option<String>,
+
+    control: bool,
+    /// Keys whose presses were handled, so that their releases are too.
+    pressed: HashSet<KeyCode>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(FRAME_RATE)
+    }
+}
+
+impl Clock {
+    pub fn new(frame_rate: f32) -> Self {
+        Self {
+            time: 0.,
+            instant: Instant::now(),
+            frame: 0,
+            frame_rate,
+
+            speed: 1.,
+            paused: false,
+
+            seek: None,
+
+            control: false,
+            pressed: HashSet::new(),
+        }
+    }
+
+    /// Returns the time and frame to render next, advancing unless paused.
+    pub fn tick(&mut self) -> (f32, i32) {
+        let now = Instant::now();
+
+        if !self.paused {
+            self.time += self.speed * (now - self.instant).as_secs_f32();
+        }
+        self.instant = now;
+
+        let frame = self.frame;
+
+        if !self.paused {
+            self.frame += 1;
+        }
+
+        (self.time, frame)
+    }
+
+    pub fn modifiers_changed(&mut self, modifiers: &Modifiers) {
+        self.control = modifiers.state().control_key();
+    }
+
+    /// Handles the key bindings, returning `true` if `event` was one of them, which should then
+    /// not reach the shader.
+    pub fn input(&mut self, event: &KeyEvent) -> bool {
+        let PhysicalKey::Code(key) = event.physical_key else {
+            return false;
+        };
+
+        if event.state == ElementState::Released {
+            return self.pressed.remove(&key);
+        }
+
+        let handled = self.press(key, event.repeat);
+
+        if handled {
+            self.pressed.insert(key);
+        }
+
+        handled
+    }
+
+    fn press(&mut self, key: KeyCode, repeat: bool) -> bool {
+        if let Some(seek) = &mut self.seek {
+            match key {
+                KeyCode::Enter | KeyCode::NumpadEnter => {
+                    if let Ok(time) = seek.parse::<f32>() {
+                        self.time = time.max(0.);
+                        self.frame = (self.time * self.frame_rate).round() as i32;
+                    }
+                    self.seek = None;
+                }
+                KeyCode::Escape => self.seek = None,
+                KeyCode::Backspace => {
+                    seek.pop();
+                }
+                _ => match seek_char(key) {
+                    Some(c) => seek.push(c),
+                    None => return false,
+                },
+            }
+
+            return true;
+        }
+
+        if !self.control {
+            return false;
+        }
+
+        match key {
+            KeyCode::Space if !repeat => self.paused = !self.paused,
+            KeyCode::ArrowRight => self.step(1),
+            KeyCode::ArrowLeft => self.step(-1),
+            KeyCode::ArrowUp => self.speed = (2. * self.speed).min(MAX_SPEED),
+            KeyCode::ArrowDown => self.speed = (self.speed / 2.).max(MIN_SPEED),
+            KeyCode::Home => {
+                self.time = 0.;
+                self.frame = 0;
+                self.speed = 1.;
+            }
+            _ => match seek_char(key) {
+                Some(c) if c.is_ascii_digit() => self.seek = Some(c.to_string()),
+                _ => return false,
+            },
+        }
+
+        true
+    }
+
+    /// Pauses and moves `frames` frames.
+    fn step(&mut self, frames: i32) {
+        self.paused = true;
+        self.time = (self.time + frames as f32 / self.frame_rate).max(0.);
+        self.frame = (self.frame + frames).max(0);
+    }
+
+    /// Describes the state for the window title, empty while playing at normal speed.
+    pub fn status(&self) -> String {
+        if let Some(seek) = &self.seek {
+            return format!("jump to {}s", seek);
+        }
+
+        let mut status = Vec::new();
+
+        if self.paused {
+            status.push(format!("paused at {:.3}s, frame {}", self.time, self.frame));
+        }
+
+        if self.speed != 1. {
+            status.push(format!("{}x speed", self.speed));
+        }
+
+        status.join(", ")
+    }
+}
+
+/// The digit or decimal point typed with `key`, on the main keys or the number pad.
+fn seek_char(key: KeyCode) -> Option<char> {
+    let c = match key {
+        KeyCode::Digit0 | KeyCode::Numpad0 => '0',
+        KeyCode::Digit1 | KeyCode::Numpad1 => '1',
+        KeyCode::Digit2 | KeyCode::Numpad2 => '2',
+        KeyCode::Digit3 | KeyCode::Numpad3 => '3',
+        KeyCode::Digit4 | KeyCode::Numpad4 => '4',
+        KeyCode::Digit5 | KeyCode::Numpad5 => '5',
+        KeyCode::Digit6 | KeyCode::Numpad6 => '6',
+        KeyCode::Digit7 | KeyCode::Numpad7 => '7',
+        KeyCode::Digit8 | KeyCode::Numpad8 => '8',
+        KeyCode::Digit9 | KeyCode::Numpad9 => '9',
+        KeyCode::Period | KeyCode::NumpadDecimal => '.',
+        _ => return None,
+    };
+
+    Some(c)
+}
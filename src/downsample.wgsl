@@ -0,0 +1,27 @@
+struct Downsample {
+    factor: u32,
+    /// Scales the average, for adding up sub-frames.
+    weight: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> downsample: Downsample;
+
+@group(0) @binding(1)
+var source: texture_2d<f32>;
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let factor = downsample.factor;
+    let origin = vec2<u32>(position.xy) * factor;
+
+    var sum = vec4<f32>(0.);
+
+    for (var y = 0u; y < factor; y += 1u) {
+        for (var x = 0u; x < factor; x += 1u) {
+            sum += textureLoad(source, origin + vec2<u32>(x, y), 0);
+        }
+    }
+
+    return sum * downsample.weight / f32(factor * factor);
+}
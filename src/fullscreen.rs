@@ -0,0 +1,135 @@
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+    window::Window,
+};
+
+/// How the window covers its monitor when fullscreen.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A borderless window the size of the monitor, which keeps its video mode.
+    #[default]
+    Borderless,
+    /// Exclusive control of the monitor, at its largest video mode.
+    Exclusive,
+}
+
+/// Switches a window between windowed and fullscreen on a chosen monitor, restoring the window's
+/// position and size when switching back.
+pub struct Fullscreen {
+    mode: FullscreenMode,
+    /// Index into the available monitors, the window's current one if not set.
+    monitor: Option<usize>,
+
+    windowed: Option<(Option<PhysicalPosition<i32>>, PhysicalSize<u32>)>,
+}
+
+impl Fullscreen {
+    pub fn new(mode: FullscreenMode, monitor: Option<usize>) -> Self {
+        Self {
+            mode,
+            monitor,
+
+            windowed: None,
+        }
+    }
+
+    /// Moves the window to the chosen monitor, keeping it windowed.
+    pub fn place(&self, window: &Window) {
+        if self.monitor.is_some() {
+            if let Some(monitor) = self.monitor(window) {
+                window.set_outer_position(monitor.position());
+            }
+        }
+    }
+
+    pub fn enter(&mut self, window: &Window) {
+        if window.fullscreen().is_some() {
+            return;
+        }
+
+        let monitor = self.monitor(window);
+
+        let fullscreen = match self.mode {
+            FullscreenMode::Borderless => winit::window::Fullscreen::Borderless(monitor),
+            FullscreenMode::Exclusive => {
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|video_mode| {
+                        let size = video_mode.size();
+
+                        (
+                            size.width * size.height,
+                            video_mode.bit_depth(),
+                            video_mode.refresh_rate_millihertz(),
+                        )
+                    })
+                });
+
+                match video_mode {
+                    Some(video_mode) => winit::window::Fullscreen::Exclusive(video_mode),
+                    None => {
+                        log::error!("No video modes to go fullscreen with, going borderless");
+
+                        winit::window::Fullscreen::Borderless(monitor)
+                    }
+                }
+            }
+        };
+
+        self.windowed = Some((window.outer_position().ok(), window.inner_size()));
+
+        window.set_fullscreen(Some(fullscreen));
+    }
+
+    pub fn exit(&mut self, window: &Window) {
+        if window.fullscreen().is_none() {
+            return;
+        }
+
+        window.set_fullscreen(None);
+
+        if let Some((position, size)) = self.windowed.take() {
+            if let Some(position) = position {
+                window.set_outer_position(position);
+            }
+
+            let _ = window.request_inner_size(size);
+        }
+    }
+
+    /// Bound to F11.
+    pub fn toggle(&mut self, window: &Window) {
+        if window.fullscreen().is_some() {
+            self.exit(window);
+        } else {
+            self.enter(window);
+        }
+    }
+
+    fn monitor(&self, window: &Window) -> Option<MonitorHandle> {
+        let Some(index) = self.monitor else {
+            return window.current_monitor();
+        };
+
+        let monitors: Vec<_> = window.available_monitors().collect();
+
+        match monitors.get(index) {
+            Some(monitor) => Some(monitor.clone()),
+            None => {
+                log::error!(
+                    "No monitor {}, only {}: {}",
+                    index,
+                    monitors.len(),
+                    monitors
+                        .iter()
+                        .enumerate()
+                        .map(|(i, monitor)| format!("{} {}", i, monitor.name().unwrap_or_default()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+
+                window.current_monitor()
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use winit::keyboard::KeyCode;
+
+const KEYS: u32 = 256;
+
+// Rows of the keyboard texture, whose columns are JavaScript key codes like Shadertoy's.
+const DOWN: usize = 0;
+const PRESSED: usize = 1;
+const TOGGLED: usize = 2;
+
+/// A 256×3 texture holding, for every key, whether it is down, whether it was pressed this
+/// frame and whether it has been pressed an odd number of times.
+pub(crate) struct Keyboard {
+    state: [[u8; KEYS as usize]; 3],
+    dirty: bool,
+
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Keyboard {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: KEYS,
+                height: 3,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            view_formats: &[],
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some("Keyboard"),
+        });
+        let view = texture.create_view(&Default::default());
+
+        Self {
+            state: [[0; KEYS as usize]; 3],
+            dirty: false,
+
+            texture,
+            view,
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        [self.texture.width(), self.texture.height()]
+    }
+
+    /// Records a key going down or up, ignoring keys without a JavaScript key code.
+    pub fn input(&mut self, key: KeyCode, down: bool) {
+        let Some(key) = key_code(key) else {
+            return;
+        };
+
+        let was_down = self.state[DOWN][key] != 0;
+
+        if down && !was_down {
+            self.state[PRESSED][key] = 255;
+            self.state[TOGGLED][key] ^= 255;
+        }
+
+        self.state[DOWN][key] = if down { 255 } else { 0 };
+        self.dirty = true;
+    }
+
+    /// Uploads the state for the next frame if it changed.
+    pub fn write(&mut self, queue: &wgpu::Queue) {
+        if !self.dirty {
+            return;
+        }
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            bytemuck::cast_slice(&self.state),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(KEYS),
+                rows_per_image: Some(3),
+            },
+            self.texture.size(),
+        );
+
+        self.dirty = false;
+    }
+
+    /// Forgets the keys pressed this frame once it has been written.
+    pub fn end_frame(&mut self) {
+        if self.state[PRESSED].iter().any(|&pressed| pressed != 0) {
+            self.state[PRESSED] = [0; KEYS as usize];
+            self.dirty = true;
+        }
+    }
+}
+
+/// Maps a physical key to the JavaScript key code Shadertoy indexes its keyboard texture with.
+fn key_code(key: KeyCode) -> Option<usize> {
+    use KeyCode::*;
+
+    Some(match key {
+        Backspace => 8,
+        Tab => 9,
+        Enter | NumpadEnter => 13,
+        ShiftLeft | ShiftRight => 16,
+        ControlLeft | ControlRight => 17,
+        AltLeft | AltRight => 18,
+        Pause => 19,
+        CapsLock => 20,
+        Escape => 27,
+        Space => 32,
+        PageUp => 33,
+        PageDown => 34,
+        End => 35,
+        Home => 36,
+        ArrowLeft => 37,
+        ArrowUp => 38,
+        ArrowRight => 39,
+        ArrowDown => 40,
+        Insert => 45,
+        Delete => 46,
+        Digit0 => 48,
+        Digit1 => 49,
+        Digit2 => 50,
+        Digit3 => 51,
+        Digit4 => 52,
+        Digit5 => 53,
+        Digit6 => 54,
+        Digit7 => 55,
+        Digit8 => 56,
+        Digit9 => 57,
+        KeyA => 65,
+        KeyB => 66,
+        KeyC => 67,
+        KeyD => 68,
+        KeyE => 69,
+        KeyF => 70,
+        KeyG => 71,
+        KeyH => 72,
+        KeyI => 73,
+        KeyJ => 74,
+        KeyK => 75,
+        KeyL => 76,
+        KeyM => 77,
+        KeyN => 78,
+        KeyO => 79,
+        KeyP => 80,
+        KeyQ => 81,
+        KeyR => 82,
+        KeyS => 83,
+        KeyT => 84,
+        KeyU => 85,
+        KeyV => 86,
+        KeyW => 87,
+        KeyX => 88,
+        KeyY => 89,
+        KeyZ => 90,
+        SuperLeft => 91,
+        SuperRight => 92,
+        ContextMenu => 93,
+        Numpad0 => 96,
+        Numpad1 => 97,
+        Numpad2 => 98,
+        Numpad3 => 99,
+        Numpad4 => 100,
+        Numpad5 => 101,
+        Numpad6 => 102,
+        Numpad7 => 103,
+        Numpad8 => 104,
+        Numpad9 => 105,
+        NumpadMultiply => 106,
+        NumpadAdd => 107,
+        NumpadSubtract => 109,
+        NumpadDecimal => 110,
+        NumpadDivide => 111,
+        F1 => 112,
+        F2 => 113,
+        F3 => 114,
+        F4 => 115,
+        F5 => 116,
+        F6 => 117,
+        F7 => 118,
+        F8 => 119,
+        F9 => 120,
+        F10 => 121,
+        F11 => 122,
+        F12 => 123,
+        NumLock => 144,
+        ScrollLock => 145,
+        Semicolon => 186,
+        Equal => 187,
+        Comma => 188,
+        Minus => 189,
+        Period => 190,
+        Slash => 191,
+        Backquote => 192,
+        BracketLeft => 219,
+        Backslash => 220,
+        BracketRight => 221,
+        Quote => 222,
+        _ => return None,
+    })
+}
@@ -1,59 +1,426 @@
+mod channels;
+mod clock;
+mod downsample;
+mod fullscreen;
+mod keyboard;
+mod mouse;
+mod output;
+mod params;
+mod profiler;
+mod shader;
+mod shadertoy;
+
+pub use channels::{
+    create_channel_bind_group_layout, Channel, BUFFER_FORMAT, CHANNELS, MAX_BUFFERS,
+};
+pub use clock::Clock;
+pub use fullscreen::{Fullscreen, FullscreenMode};
+pub use output::{Format, Frame, ImageFormat, Output, PngRows, Precision, Rgba32FImage};
+pub use params::{Param, ParamType, Params, ParamsFile};
+pub use shader::{fragment_entry_point, glsl, Language, ShaderFile};
+pub use shadertoy::{is_shadertoy, shadertoy, shadertoy_buffer};
+
+use channels::Channels;
+use downsample::{Downsample, ACCUMULATOR_FORMAT};
+use mouse::Mouse;
+use params::params_size;
+use profiler::Profiler;
+
 use winit::{
     error::{EventLoopError, OsError},
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
     event_loop::{EventLoop, EventLoopBuilder},
+    keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowBuilder},
 };
 
 use wgpu::util::DeviceExt;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use simple_logger::SimpleLogger;
+
+const FRAME_RATE: f32 = 30.;
+const DURATION: f32 = 10.;
+/// Frames read back at once while recording, so that the GPU renders the next frame while the
+/// previous ones are still being copied out.
+const READBACK_BUFFERS: usize = 3;
 
 #[derive(Parser, Debug)]
 #[command(version)]
 pub struct Options {
+    /// Width of the window, or of the recorded frames.
     #[arg(long, default_value_t = 512)]
     pub width: u32,
 
+    /// Height of the window, or of the recorded frames.
     #[arg(long, default_value_t = 512)]
     pub height: u32,
 
     #[arg(long)]
     pub record: bool,
 
+    /// Record this many frames, ten seconds' worth by default.
+    #[arg(long, conflicts_with = "duration")]
+    pub frames: Option<u32>,
+
+    /// Record this many seconds.
+    #[arg(long, value_name = "SECONDS", value_parser = positive)]
+    pub duration: Option<f32>,
+
+    /// Frames per second to record at, or to step through in a window.
+    #[arg(long, default_value_t = FRAME_RATE, value_parser = positive)]
+    pub fps: f32,
+
+    /// Start recording this many seconds in.
+    #[arg(long, value_name = "SECONDS", default_value_t = 0., value_parser = non_negative)]
+    pub start_time: f32,
+
+    /// Directory to record numbered files into, created if missing.
+    #[arg(long, value_name = "DIR", default_value = "images")]
+    pub output_dir: std::path::PathBuf,
+
+    /// Format of the numbered files in `--output-dir`.
+    #[arg(long, value_enum, default_value_t = ImageFormat::Png)]
+    pub image_format: ImageFormat,
+
+    /// Render recordings at this many times the width and height and average each block of
+    /// pixels, to smooth edges.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub supersample: u32,
+
+    /// Record one still as a PNG instead, rendered in tiles of at most SIZE×SIZE pixels and
+    /// stitched together, for stills larger than a texture can hold. Shaders other than
+    /// Shadertoy ones add the uniforms' `offset` to their fragment position to draw each tile.
+    /// Buffers cannot be tiled.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        requires = "record",
+        conflicts_with = "buffers",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub tile: Option<u32>,
+
+    /// Blur recorded motion by averaging this many sub-frames spread over the shutter.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub motion_blur: u32,
+
+    /// Portion of each frame's interval the shutter stays open for with `--motion-blur`, in
+    /// degrees, where 360 blurs over the whole interval.
+    #[arg(long, value_name = "DEGREES", default_value_t = 180., value_parser = shutter_angle)]
+    pub shutter_angle: f32,
+
+    /// Precision to record at, by default floats for EXR and HDR files and 8 bits otherwise.
+    #[arg(long, value_enum)]
+    pub precision: Option<Precision>,
+
+    /// Record into this file, or stdout for `-`, instead of numbered files in `--output-dir`.
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<std::path::PathBuf>,
+
+    /// Format of `--output`, guessed from its extension by default.
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Load the fragment shader from this file instead of the built-in one, reloading it
+    /// whenever it changes while the window is open.
+    #[arg(long)]
+    pub shader: Option<std::path::PathBuf>,
+
+    /// Render this shader file into an offscreen buffer before the image every frame. Can be
+    /// given up to four times.
+    #[arg(long = "buffer", value_name = "PATH")]
+    pub buffers: Vec<std::path::PathBuf>,
+
+    /// Bind `buffer-a` to `buffer-d`, `keyboard` or an image file to the next channel, starting
+    /// at channel 0. Can be given up to four times; by default the n-th buffer is bound to
+    /// channel n.
+    #[arg(long = "channel", value_name = "CHANNEL")]
+    pub channels: Vec<Channel>,
+
+    /// Read custom uniforms from this TOML file, by default the `.toml` file named like
+    /// `--shader` next to it if there is one. Reloaded whenever it changes while the window is
+    /// open.
+    #[arg(long, value_name = "PATH")]
+    pub params: Option<std::path::PathBuf>,
+
+    /// Open the window fullscreen, borderless by default. F11 toggles fullscreen either way.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "borderless")]
+    pub fullscreen: Option<FullscreenMode>,
+
+    /// Open the window on this monitor, counting from 0, instead of the current one.
+    #[arg(long, value_name = "INDEX")]
+    pub monitor: Option<usize>,
+
+    /// How the window presents frames, by default Mailbox where supported and Fifo otherwise.
+    #[arg(long, value_enum)]
+    pub present_mode: Option<PresentMode>,
+
+    /// Show how long frames take on the CPU and GPU in the window title, and log it with
+    /// `--verbose`.
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Re-render screenshots taken with F12 at this size instead of the window's. They are saved
+    /// as PNG files named after the time they are taken in `--output-dir`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        num_args = 2,
+        value_names = ["WIDTH", "HEIGHT"],
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub screenshot_size: Option<Vec<u32>>,
+
+    /// Hold the left mouse button down at this position, in pixels from the top-left corner,
+    /// while recording. Otherwise recordings see a mouse that was never clicked.
+    #[arg(long, value_delimiter = ',', num_args = 2, value_names = ["X", "Y"])]
+    pub mouse: Option<Vec<f32>>,
+
+    /// Language of the `--shader` and `--buffer` files, guessed from their extension and
+    /// contents by default.
+    #[arg(long, value_enum)]
+    pub lang: Option<Language>,
+
     #[arg(long)]
     pub verbose: bool,
 }
 
+/// Parses a finite number greater than 0.
+fn positive(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(value) if value.is_finite() && value > 0. => Ok(value),
+        Ok(_) => Err("must be greater than 0".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Parses a finite number of at least 0.
+fn non_negative(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(value) if value.is_finite() && value >= 0. => Ok(value),
+        Ok(_) => Err("must be at least 0".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Parses an angle greater than 0 and at most 360 degrees.
+fn shutter_angle(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(value) if value > 0. && value <= 360. => Ok(value),
+        Ok(_) => Err("must be greater than 0 and at most 360".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl Options {
+    /// Checks what clap's attributes cannot, for [`run`] to exit with a usage error like clap.
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        let too_many = |arg: &str, max: usize| {
+            Self::command().error(
+                clap::error::ErrorKind::TooManyValues,
+                format!("`{}` can be given at most {} times", arg, max),
+            )
+        };
+
+        if self.buffers.len() > MAX_BUFFERS {
+            return Err(too_many("--buffer", MAX_BUFFERS));
+        }
+
+        if self.channels.len() > CHANNELS {
+            return Err(too_many("--channel", CHANNELS));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of frames to record, from `--frames` or `--duration`.
+    pub fn frames(&self) -> u32 {
+        self.frames
+            .unwrap_or_else(|| (self.duration.unwrap_or(DURATION) * self.fps).ceil() as u32)
+    }
+
+    /// Returns `--precision`, or the one fitting the output format.
+    pub fn precision(&self) -> Precision {
+        self.precision.unwrap_or(match self.image_format {
+            ImageFormat::Exr | ImageFormat::Hdr if self.output.is_none() => Precision::F32,
+            _ => Precision::U8,
+        })
+    }
+}
+
+/// The uniforms bound at group 0, binding 0, which shaders read through a struct declaring a
+/// prefix of these fields in order:
+///
+/// ```wgsl
+/// struct Uniforms {
+///     resolution: vec2<f32>,
+///     time: f32,
+///     time_delta: f32,
+///     frame: i32,
+///     frame_rate: f32,
+///     aspect: f32,
+///     pixel_ratio: f32,
+///     mouse: vec4<f32>,
+///     date: vec4<f32>,
+///     channel_resolution: array<vec4<f32>, 4>,
+///     offset: vec2<f32>,
+/// };
+/// ```
+///
+/// Fields are ordered so that WGSL's alignment rules leave no padding between them, which the
+/// assertions below check.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Uniform {
+    pub resolution: [f32; 2],
+
+    /// Seconds since the start.
     pub time: f32,
 
-    _padding: u32,
+    /// Seconds since the previous frame, one frame interval while recording, the same for
+    /// every sub-frame with motion blur.
+    pub time_delta: f32,
 
-    pub resolution: [f32; 2],
+    /// Frames rendered before this one.
+    pub frame: i32,
+
+    /// Frames per second, measured in a window and fixed while recording.
+    pub frame_rate: f32,
+
+    /// Width divided by height.
+    pub aspect: f32,
+
+    /// Physical pixels per logical pixel, i.e. the window's scale factor.
+    pub pixel_ratio: f32,
+
+    /// Shadertoy's `iMouse`, in pixels from the top-left corner: the position while the left
+    /// button is dragged, and the last click position with `z` negative once the button is
+    /// released and `w` negative after the frame it was clicked on. All zero until clicked.
+    pub mouse: [f32; 4],
+
+    /// Shadertoy's `iDate` in UTC: year, month starting at 0, day and seconds since midnight.
+    pub date: [f32; 4],
+
+    /// Shadertoy's `iChannelResolution`: width, height and 1 for every bound channel.
+    pub channel_resolution: [[f32; 4]; CHANNELS],
+
+    /// Position of the rendered tile in the image, in pixels from the top-left corner, which
+    /// shaders add to `@builtin(position)` to be rendered in tiles. Zero unless tiled.
+    pub offset: [f32; 2],
+
+    _padding: [f32; 2],
 }
 
+// Offsets WGSL gives the fields of `struct Uniforms`: `vec2` aligns to 8 bytes, scalars to 4,
+// `vec4` and arrays of `vec4` to 16, and the struct's size rounds up to its largest alignment.
+const _: () = {
+    use std::mem::{offset_of, size_of};
+
+    assert!(offset_of!(Uniform, resolution) == 0);
+    assert!(offset_of!(Uniform, time) == 8);
+    assert!(offset_of!(Uniform, time_delta) == 12);
+    assert!(offset_of!(Uniform, frame) == 16);
+    assert!(offset_of!(Uniform, frame_rate) == 20);
+    assert!(offset_of!(Uniform, aspect) == 24);
+    assert!(offset_of!(Uniform, pixel_ratio) == 28);
+    assert!(offset_of!(Uniform, mouse) == 32);
+    assert!(offset_of!(Uniform, date) == 48);
+    assert!(offset_of!(Uniform, channel_resolution) == 64);
+    assert!(offset_of!(Uniform, offset) == 128);
+    assert!(size_of::<Uniform>() == 144);
+};
+
 impl Uniform {
     fn new(width: u32, height: u32) -> Self {
         Self {
+            resolution: [width as f32, height as f32],
+
             time: 0.,
 
-            _padding: 0,
+            time_delta: 0.,
 
-            resolution: [width as f32, height as f32],
+            frame: 0,
+
+            frame_rate: 0.,
+
+            aspect: width as f32 / height as f32,
+
+            pixel_ratio: 1.,
+
+            mouse: [0.; 4],
+
+            date: date(std::time::SystemTime::now()),
+
+            channel_resolution: [[0.; 4]; CHANNELS],
+
+            offset: [0.; 2],
+
+            _padding: [0.; 2],
         }
     }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.resolution = [width as f32, height as f32];
+        self.aspect = width as f32 / height as f32;
+    }
+}
+
+fn date(time: std::time::SystemTime) -> [f32; 4] {
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let days = (since_epoch.as_secs() / 86400) as i64;
+    let seconds = since_epoch.as_secs_f64() - (days * 86400) as f64;
+
+    // Converts days since 1970-01-01 to a proleptic Gregorian date, with years starting in March
+    // so that leap days fall at the end of the year.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12;
+    let year = year_of_era + era * 400 + i64::from(month < 2);
+
+    [year as f32, month as f32, day as f32, seconds as f32]
 }
 
 pub trait WindowState {
     fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>);
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError>;
+    fn cursor_moved(&mut self, _position: winit::dpi::PhysicalPosition<f64>) {}
+    fn mouse_input(&mut self, _state: ElementState, _button: MouseButton) {}
+    fn keyboard_input(&mut self, _event: &KeyEvent) {}
+    fn scale_factor_changed(&mut self, _scale_factor: f64) {}
+    /// Renders `frame` at `time` seconds, as kept by the [`Clock`] in [`render`].
+    fn render(&mut self, time: f32, frame: i32) -> Result<(), wgpu::SurfaceError>;
+    /// Saves the last rendered frame, bound to F12.
+    fn screenshot(&mut self) {}
+    /// Returns a report of how long the last frames took whenever there is a new one.
+    fn timings(&mut self) -> Option<String> {
+        None
+    }
 }
 
 pub trait RecordState {
     fn record(&mut self, i: i32);
+
+    /// Called after the last frame was recorded.
+    fn finish(&mut self) {}
 }
 
 pub fn create_event_loop() -> Result<EventLoop<()>, EventLoopError> {
@@ -102,7 +469,11 @@ pub async fn create_device_and_queue(adapter: &wgpu::Adapter) -> (wgpu::Device,
         .request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                features: wgpu::Features::empty(),
+                // For `--timings`, which times frames on the CPU alone without them, and for
+                // sample counts beyond the ones every device supports.
+                features: adapter.features()
+                    & (wgpu::Features::TIMESTAMP_QUERY
+                        | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
                 limits: wgpu::Limits::default(),
             },
             None,
@@ -111,10 +482,13 @@ pub async fn create_device_and_queue(adapter: &wgpu::Adapter) -> (wgpu::Device,
         .unwrap()
 }
 
+/// Creates the buffer for [`Uniform`] at binding 0 of group 0, and the group's layout and bind
+/// group, which also binds `params_buffer` at binding 1.
 pub fn create_uniforms(
     device: &wgpu::Device,
     width: u32,
     height: u32,
+    params_buffer: &wgpu::Buffer,
 ) -> (
     Uniform,
     wgpu::Buffer,
@@ -131,8 +505,8 @@ pub fn create_uniforms(
 
     let uniform_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
+            entries: &[0, 1].map(|binding| wgpu::BindGroupLayoutEntry {
+                binding,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
@@ -140,53 +514,82 @@ pub fn create_uniforms(
                     min_binding_size: None,
                 },
                 count: None,
-            }],
+            }),
             label: Some("Uniform Bind Group Layout"),
         });
 
-    let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &uniform_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: uniform_buffer.as_entire_binding(),
-        }],
-        label: Some("Uniform Bind Group"),
-    });
+    let uniform_bind_group = create_uniform_bind_group(
+        device,
+        &uniform_bind_group_layout,
+        &uniform_buffer,
+        params_buffer,
+    );
 
-    return (
+    (
         uniform,
         uniform_buffer,
         uniform_bind_group_layout,
         uniform_bind_group,
-    );
+    )
+}
+
+pub fn create_uniform_bind_group(
+    device: &wgpu::Device,
+    uniform_bind_group_layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    params_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: uniform_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: params_buffer.as_entire_binding(),
+            },
+        ],
+        label: Some("Uniform Bind Group"),
+    })
 }
 
+/// Creates the buffer holding the defaults of `params`, see [`Params::bytes`].
+pub fn create_params_buffer(device: &wgpu::Device, params: &Params) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Params"),
+        contents: &params.bytes(),
+        usage: wgpu::BufferUsages::UNIFORM,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_render_pipeline(
     device: &wgpu::Device,
-    render_pipeline_layout: wgpu::PipelineLayout,
-    vert_shader: wgpu::ShaderModule,
-    frag_shader: wgpu::ShaderModule,
+    render_pipeline_layout: &wgpu::PipelineLayout,
+    vert_shader: &wgpu::ShaderModule,
+    frag_shader: &wgpu::ShaderModule,
+    frag_entry_point: &str,
     vertex_buffers: &[wgpu::VertexBufferLayout<'_>],
-    headless: bool,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
 ) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
+        layout: Some(render_pipeline_layout),
         vertex: wgpu::VertexState {
-            module: &vert_shader,
+            module: vert_shader,
             entry_point: "vs_main",
             buffers: vertex_buffers,
         },
         fragment: Some(wgpu::FragmentState {
-            module: &frag_shader,
-            entry_point: "fs_main",
+            module: frag_shader,
+            entry_point: frag_entry_point,
             targets: &[Some(wgpu::ColorTargetState {
-                format: if headless {
-                    wgpu::TextureFormat::Rgba8Unorm
-                } else {
-                    wgpu::TextureFormat::Bgra8Unorm
-                },
-                blend: Some(wgpu::BlendState::REPLACE),
+                format,
+                // Like `BlendState::REPLACE`, but also valid for `Rgba32Float`, which cannot blend.
+                blend: None,
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -201,7 +604,7 @@ pub fn create_render_pipeline(
         },
         depth_stencil: None,
         multisample: wgpu::MultisampleState {
-            count: 1,
+            count: sample_count,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -212,12 +615,23 @@ pub fn create_render_pipeline(
 pub fn create_render_pass<'a>(
     encoder: &'a mut wgpu::CommandEncoder,
     view: &'a wgpu::TextureView,
+) -> wgpu::RenderPass<'a> {
+    create_resolving_render_pass(encoder, view, None)
+}
+
+/// Like [`create_render_pass`], but resolves `view`, when it is multisampled, into
+/// `resolve_target`. Pipelines drawing into it need the same sample count, see
+/// [`create_multisampled_view`].
+pub fn create_resolving_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    resolve_target: Option<&'a wgpu::TextureView>,
 ) -> wgpu::RenderPass<'a> {
     encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Render Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
             view,
-            resolve_target: None,
+            resolve_target,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 store: wgpu::StoreOp::Store,
@@ -230,40 +644,181 @@ pub fn create_render_pass<'a>(
     })
 }
 
-pub fn surface_config(width: u32, height: u32) -> wgpu::SurfaceConfiguration {
+/// Creates a texture with `sample_count` samples per pixel for geometry pipelines to render into
+/// with MSAA before resolving it, see [`create_resolving_render_pass`]. The count must be one
+/// `format` supports, see [`supported_sample_count`].
+pub fn create_multisampled_view(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            view_formats: &[],
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Multisampled"),
+        })
+        .create_view(&Default::default())
+}
+
+/// Returns the most samples per pixel, up to `requested`, that `device` can multisample `format`
+/// with, 1 if it cannot.
+pub fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    requested: u32,
+) -> u32 {
+    let features = if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(device.features())
+    };
+
+    max_sample_count(features.flags, requested)
+}
+
+fn max_sample_count(flags: wgpu::TextureFormatFeatureFlags, requested: u32) -> u32 {
+    [16, 8, 4, 2]
+        .into_iter()
+        .find(|&count| count <= requested && flags.sample_count_supported(count))
+        .unwrap_or(1)
+}
+
+/// How frames are queued for the display.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Waits for vertical blank, always supported.
+    Fifo,
+    /// Waits for vertical blank, replacing the queued frame with newer ones.
+    Mailbox,
+    /// Presents right away, which may tear.
+    Immediate,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// Configures `surface` with a format and present mode it supports on `adapter`.
+///
+/// The format is 8 bits per channel and not sRGB where possible, since shaders write sRGB values
+/// like on Shadertoy. sRGB formats are rendered to through a view without the sRGB encoding where
+/// the adapter allows it; pipelines target the first of `view_formats` either way.
+///
+/// The present mode is `present_mode`, or Mailbox by default, falling back to Fifo.
+pub fn surface_config(
+    surface: &wgpu::Surface,
+    adapter: &wgpu::Adapter,
+    width: u32,
+    height: u32,
+    present_mode: Option<PresentMode>,
+) -> wgpu::SurfaceConfiguration {
+    let capabilities = surface.get_capabilities(adapter);
+
+    let format = capabilities
+        .formats
+        .iter()
+        .copied()
+        .filter(|format| {
+            matches!(
+                format.remove_srgb_suffix(),
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Rgba8Unorm
+            )
+        })
+        .min_by_key(|format| format.is_srgb())
+        .unwrap_or(capabilities.formats[0]);
+
+    let supported =
+        |present_mode: wgpu::PresentMode| capabilities.present_modes.contains(&present_mode);
+
+    let present_mode = match present_mode.map(wgpu::PresentMode::from) {
+        Some(present_mode) if supported(present_mode) => present_mode,
+        Some(present_mode) => {
+            log::error!("{:?} is unsupported, presenting with Fifo", present_mode);
+
+            wgpu::PresentMode::Fifo
+        }
+        None if supported(wgpu::PresentMode::Mailbox) => wgpu::PresentMode::Mailbox,
+        None => wgpu::PresentMode::Fifo,
+    };
+
+    let view_format = if adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS)
+    {
+        format.remove_srgb_suffix()
+    } else {
+        format
+    };
+
+    log::info!("Presenting {:?} with {:?}", view_format, present_mode);
+
     wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: wgpu::TextureFormat::Bgra8Unorm,
+        format,
         width,
         height,
-        present_mode: wgpu::PresentMode::Mailbox,
+        present_mode,
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
-        view_formats: vec![],
+        view_formats: vec![view_format],
     }
 }
 
-pub fn create_texture_desc(texture_size: u32) -> wgpu::TextureDescriptor<'static> {
+pub fn create_texture_desc(
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::TextureDescriptor<'static> {
     wgpu::TextureDescriptor {
         size: wgpu::Extent3d {
-            width: texture_size,
-            height: texture_size,
+            width,
+            height,
             depth_or_array_layers: 1,
         },
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         view_formats: &[],
-        format: wgpu::TextureFormat::Rgba8Unorm,
+        format,
         usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
         label: None,
     }
 }
 
+/// Returns the bytes between rows of an image `width` pixels wide in `format` copied into a
+/// buffer, which wgpu requires to be a multiple of [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`].
+pub fn padded_bytes_per_row(width: u32, format: wgpu::TextureFormat) -> u32 {
+    (format.block_size(None).unwrap() * width).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
 pub fn copy_texture_to_buffer(
     encoder: &mut wgpu::CommandEncoder,
     texture: &wgpu::Texture,
     output_buffer: &wgpu::Buffer,
-    texture_size: u32,
+    width: u32,
+    height: u32,
 ) {
     encoder.copy_texture_to_buffer(
         wgpu::ImageCopyTexture {
@@ -276,20 +831,24 @@ pub fn copy_texture_to_buffer(
             buffer: output_buffer,
             layout: wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * texture_size),
-                rows_per_image: Some(texture_size),
+                bytes_per_row: Some(padded_bytes_per_row(width, texture.format())),
+                rows_per_image: Some(height),
             },
         },
         wgpu::Extent3d {
-            width: texture_size,
-            height: texture_size,
+            width,
+            height,
             depth_or_array_layers: 1,
         },
     );
 }
 
-pub fn create_output_buffer_desc(texture_size: u32) -> wgpu::BufferDescriptor<'static> {
-    let output_buffer_size = (4 * texture_size * texture_size) as wgpu::BufferAddress;
+pub fn create_output_buffer_desc(
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::BufferDescriptor<'static> {
+    let output_buffer_size = (padded_bytes_per_row(width, format) * height) as wgpu::BufferAddress;
     wgpu::BufferDescriptor {
         size: output_buffer_size,
         usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
@@ -298,12 +857,15 @@ pub fn create_output_buffer_desc(texture_size: u32) -> wgpu::BufferDescriptor<'s
     }
 }
 
-pub async fn save_buffer_as_image(
+/// Reads back an 8-bit RGBA or BGRA image copied by [`copy_texture_to_buffer`], dropping the
+/// padding at the end of its rows, and unmaps the buffer again.
+pub async fn read_buffer_to_image(
     output_buffer: &wgpu::Buffer,
     device: &wgpu::Device,
-    texture_size: u32,
-    name: &str,
-) {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> image::ImageResult<image::RgbaImage> {
     let buffer_slice = output_buffer.slice(..);
 
     let (sender, receiver) = flume::bounded(1);
@@ -315,38 +877,1483 @@ pub async fn save_buffer_as_image(
     device.poll(wgpu::Maintain::Wait);
     receiver.recv_async().await.unwrap().unwrap();
 
-    let data = buffer_slice.get_mapped_range();
+    Ok(read_frame(output_buffer, width, height, format)?.into_rgba8())
+}
+
+/// Reads a mapped buffer holding an image copied by [`copy_texture_to_buffer`] and unmaps it.
+fn read_frame(
+    output_buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> image::ImageResult<Frame> {
+    let frame = Frame::from_bytes(
+        &output_buffer.slice(..).get_mapped_range(),
+        padded_bytes_per_row(width, format) as usize,
+        width,
+        height,
+        format,
+    );
+
+    output_buffer.unmap();
 
-    use image::{ImageBuffer, Rgba};
-    let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(texture_size, texture_size, data).unwrap();
-    buffer.save(format!("images/{}.png", name)).unwrap();
+    frame
 }
 
-pub fn render(
-    event_loop: EventLoop<()>,
-    window: Window,
-    mut state: impl WindowState,
-) -> Result<(), EventLoopError> {
-    let state_window_id = window.id();
+pub async fn save_buffer_as_image(
+    output_buffer: &wgpu::Buffer,
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    path: &std::path::Path,
+) -> image::ImageResult<()> {
+    read_buffer_to_image(output_buffer, device, width, height, format)
+        .await?
+        .save(path)
+}
 
-    event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent { event, window_id } if window_id == state_window_id => match event {
-            WindowEvent::CloseRequested => elwt.exit(),
-            WindowEvent::Resized(physical_size) => state.resize(physical_size),
-            WindowEvent::RedrawRequested => match state.render() {
-                Ok(_) => {}
-                _ => elwt.exit(),
-            },
-            _ => (),
-        },
+enum Target {
+    Window {
+        surface: wgpu::Surface,
+        config: wgpu::SurfaceConfiguration,
+    },
+    Record {
+        texture: wgpu::Texture,
+        texture_view: wgpu::TextureView,
+        output_buffers: Vec<wgpu::Buffer>,
+        width: u32,
+        height: u32,
+    },
+}
 
-        Event::AboutToWait => window.request_redraw(),
-        _ => (),
-    })
+/// A frame copied into one of the output buffers, waiting for the buffer to be mapped.
+struct Readback {
+    frame: i32,
+    buffer: usize,
+    submission: wgpu::SubmissionIndex,
+    mapped: flume::Receiver<Result<(), wgpu::BufferAsyncError>>,
 }
 
-pub fn record(mut state: impl RecordState, frames: i32) {
-    for i in 0..frames {
-        state.record(i);
+/// Renders a fragment shader over a full-screen triangle, after any buffers it samples, either
+/// into a window or into offscreen textures saved as images. Buffers and images are bound to the
+/// shaders as channels in bind group 1, see [`create_channel_bind_group_layout`], and custom
+/// uniforms at binding 1 of group 0, see [`Params`].
+pub struct ShaderRunner {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+
+    target: Target,
+    format: wgpu::TextureFormat,
+
+    vert_shader: wgpu::ShaderModule,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    /// Bytes of parameters the image shader reads.
+    params_size: u64,
+
+    shader_file: Option<ShaderFile>,
+    shadertoy: bool,
+
+    channels: Channels,
+
+    mouse: Mouse,
+
+    started: std::time::SystemTime,
+    /// When the uniforms were last updated.
+    updated: std::time::Instant,
+    /// Fixed while recording, otherwise measured.
+    frame_rate: Option<f32>,
+    /// Time of the first recorded frame.
+    start_time: f32,
+
+    params_file: Option<ParamsFile>,
+    params_buffer: wgpu::Buffer,
+
+    profiler: Option<Profiler>,
+    /// The profiler's latest report, until it is taken.
+    timings: Option<String>,
+
+    screenshot_dir: std::path::PathBuf,
+    /// The window's size unless set.
+    screenshot_size: Option<[u32; 2]>,
+
+    supersample: u32,
+    /// Number of sub-frames averaged into each recorded frame.
+    sub_frames: u32,
+    /// Portion of the frame interval sub-frames are spread over.
+    shutter: f32,
+    downsample: Option<Downsample>,
+    /// Adds up sub-frames and resolves their sum into the target.
+    accumulator: Option<Downsample>,
+    output: Option<Output>,
+    readbacks: std::collections::VecDeque<Readback>,
+
+    uniform: Uniform,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    uniform_bind_group: wgpu::BindGroup,
+}
+
+impl ShaderRunner {
+    pub async fn windowed(
+        window: &Window,
+        frag_shader_desc: wgpu::ShaderModuleDescriptor<'_>,
+        present_mode: Option<PresentMode>,
+    ) -> Self {
+        let size = window.inner_size();
+
+        let instance = create_instance();
+
+        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+
+        let adapter = create_adapter(&instance, Some(&surface)).await;
+
+        let (device, queue) = create_device_and_queue(&adapter).await;
+
+        let config = surface_config(&surface, &adapter, size.width, size.height, present_mode);
+
+        surface.configure(&device, &config);
+
+        let target = Target::Window { surface, config };
+
+        let mut state = Self::new(
+            device,
+            queue,
+            target,
+            frag_shader_desc,
+            size.width,
+            size.height,
+        );
+
+        state.uniform.pixel_ratio = window.scale_factor() as f32;
+
+        state
+    }
+
+    pub async fn headless(
+        frag_shader_desc: wgpu::ShaderModuleDescriptor<'_>,
+        width: u32,
+        height: u32,
+        precision: Precision,
+    ) -> Self {
+        let instance = create_instance();
+
+        let adapter = create_adapter(&instance, None).await;
+
+        let (device, queue) = create_device_and_queue(&adapter).await;
+
+        let format = precision.texture_format();
+
+        let texture = device.create_texture(&create_texture_desc(width, height, format));
+        let texture_view = texture.create_view(&Default::default());
+
+        let output_buffers = (0..READBACK_BUFFERS)
+            .map(|_| device.create_buffer(&create_output_buffer_desc(width, height, format)))
+            .collect();
+
+        let target = Target::Record {
+            texture,
+            texture_view,
+            output_buffers,
+            width,
+            height,
+        };
+
+        let mut state = Self::new(device, queue, target, frag_shader_desc, width, height);
+
+        state.frame_rate = Some(FRAME_RATE);
+
+        state
+    }
+
+    fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        target: Target,
+        frag_shader_desc: wgpu::ShaderModuleDescriptor<'_>,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shadertoy = is_shadertoy(&frag_shader_desc.source);
+        let frag_entry_point = fragment_entry_point(&frag_shader_desc.source);
+        let (frag_shader_desc, module) = shader::parse(frag_shader_desc)
+            .unwrap_or_else(|err| panic!("Failed to compile the fragment shader:\n{}", err));
+        let params_size = params_size(&module);
+        let frag_shader = device.create_shader_module(frag_shader_desc);
+        let vert_shader = device.create_shader_module(wgpu::include_wgsl!("vertex.wgsl"));
+
+        let params_buffer = create_params_buffer(&device, &Params::default());
+
+        let (uniform, uniform_buffer, uniform_bind_group_layout, uniform_bind_group) =
+            create_uniforms(&device, width, height, &params_buffer);
+
+        let channels = Channels::new(&device, &queue, width, height);
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout, channels.bind_group_layout()],
+                push_constant_ranges: &[],
+            });
+
+        let format = match &target {
+            Target::Window { config, .. } => config.view_formats[0],
+            Target::Record { texture, .. } => texture.format(),
+        };
+
+        let render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &vert_shader,
+            &frag_shader,
+            frag_entry_point,
+            &[],
+            format,
+            1,
+        );
+
+        Self {
+            device,
+            queue,
+
+            target,
+            format,
+
+            vert_shader,
+            render_pipeline_layout,
+            render_pipeline,
+            params_size,
+
+            shader_file: None,
+            shadertoy,
+
+            channels,
+
+            mouse: Mouse::default(),
+
+            started: std::time::SystemTime::now(),
+            updated: std::time::Instant::now(),
+            frame_rate: None,
+            start_time: 0.,
+
+            params_file: None,
+            params_buffer,
+
+            profiler: None,
+            timings: None,
+
+            screenshot_dir: "images".into(),
+            screenshot_size: None,
+
+            supersample: 1,
+            sub_frames: 1,
+            shutter: 0.5,
+            downsample: None,
+            accumulator: None,
+            output: None,
+            readbacks: Default::default(),
+
+            uniform,
+            uniform_buffer,
+            uniform_bind_group_layout,
+            uniform_bind_group,
+        }
+    }
+
+    /// Recompiles the fragment shader from `shader_file` whenever the file changes.
+    pub fn watch(&mut self, shader_file: ShaderFile) {
+        self.shader_file = Some(shader_file);
+    }
+
+    /// Renders the shader from `shader_file` into the next buffer before the image every frame,
+    /// recompiling it whenever the file changes.
+    pub fn add_buffer(&mut self, shader_file: ShaderFile) {
+        let frag_shader_desc = shader_file.load().unwrap_or_else(|err| {
+            panic!("Failed to read {}: {}", shader_file.path().display(), err)
+        });
+
+        let frag_entry_point = fragment_entry_point(&frag_shader_desc.source);
+        let (frag_shader_desc, module) = shader::parse(frag_shader_desc).unwrap_or_else(|err| {
+            panic!(
+                "Failed to compile {}:\n{}",
+                shader_file.path().display(),
+                err
+            )
+        });
+        let params_size = params_size(&module);
+        let frag_shader = self.device.create_shader_module(frag_shader_desc);
+
+        let render_pipeline = create_render_pipeline(
+            &self.device,
+            &self.render_pipeline_layout,
+            &self.vert_shader,
+            &frag_shader,
+            frag_entry_point,
+            &[],
+            BUFFER_FORMAT,
+            1,
+        );
+
+        self.channels
+            .push_buffer(&self.device, shader_file, render_pipeline, params_size);
+        self.uniform.channel_resolution = self.channels.resolutions();
+    }
+
+    /// Binds `channels[i]` to channel `i` instead of buffer `i`, loading images from disk.
+    ///
+    /// Images are flipped vertically for Shadertoy shaders, whose texture coordinates start at
+    /// the bottom.
+    pub fn set_channels(&mut self, channels: Vec<Channel>) -> image::ImageResult<()> {
+        self.channels
+            .assign(&self.device, &self.queue, channels, self.shadertoy)?;
+        self.uniform.channel_resolution = self.channels.resolutions();
+
+        Ok(())
+    }
+
+    /// Binds the parameters from `params_file`, reloading them whenever the file changes.
+    pub fn set_params(&mut self, params_file: ParamsFile) -> std::io::Result<()> {
+        let params = params_file.load()?;
+
+        self.bind_params(&params)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        self.params_file = Some(params_file);
+
+        Ok(())
+    }
+
+    /// Bytes of parameters the image and buffer shaders read, which the bound ones must cover.
+    fn params_read(&self) -> u64 {
+        self.channels
+            .buffers()
+            .map(|buffer| buffer.params_size)
+            .fold(self.params_size, u64::max)
+    }
+
+    /// Swaps in `params`, unless the shaders read more than they hold.
+    fn bind_params(&mut self, params: &Params) -> Result<(), String> {
+        let size = params.bytes().len() as u64;
+        let read = self.params_read();
+
+        if size < read {
+            return Err(format!(
+                "the shaders read {} bytes of parameters but these only take {}",
+                read, size
+            ));
+        }
+
+        log::info!("Binding parameters as\n{}", params.wgsl());
+
+        self.params_buffer = create_params_buffer(&self.device, params);
+        self.uniform_bind_group = create_uniform_bind_group(
+            &self.device,
+            &self.uniform_bind_group_layout,
+            &self.uniform_buffer,
+            &self.params_buffer,
+        );
+
+        Ok(())
+    }
+
+    /// Swaps in pipelines built from the watched shader files that changed, keeping the current
+    /// pipelines when the new sources fail to load or compile.
+    fn reload(&mut self) {
+        if let Some(params_file) = &mut self.params_file {
+            if params_file.changed() {
+                let path = params_file.path().display().to_string();
+                let result = params_file.load().map_err(|err| err.to_string());
+
+                if let Err(err) = result.and_then(|params| self.bind_params(&params)) {
+                    log::error!("Failed to reload {}: {}", path, err);
+                }
+            }
+        }
+
+        let bound = self.params_buffer.size();
+
+        if let Some(shader_file) = &mut self.shader_file {
+            if let Some((render_pipeline, params_size)) = reload_pipeline(
+                &self.device,
+                &self.render_pipeline_layout,
+                &self.vert_shader,
+                shader_file,
+                self.format,
+                bound,
+            ) {
+                self.render_pipeline = render_pipeline;
+                self.params_size = params_size;
+            }
+        }
+
+        for buffer in self.channels.buffers_mut() {
+            if let Some((render_pipeline, params_size)) = reload_pipeline(
+                &self.device,
+                &self.render_pipeline_layout,
+                &self.vert_shader,
+                &mut buffer.shader_file,
+                BUFFER_FORMAT,
+                bound,
+            ) {
+                buffer.render_pipeline = render_pipeline;
+                buffer.params_size = params_size;
+            }
+        }
+    }
+
+    /// Holds the left mouse button down at `x`, `y`, in pixels from the top-left corner.
+    pub fn hold_mouse(&mut self, x: f32, y: f32) {
+        let factor = self.supersample as f32;
+
+        self.mouse.moved(factor * x, factor * y);
+        self.mouse.pressed();
+    }
+
+    /// Renders recorded frames at `factor` times their width and height and averages every
+    /// `factor`×`factor` block of pixels, so shaders see the larger resolution.
+    pub fn set_supersample(&mut self, factor: u32) {
+        let Target::Record { width, height, .. } = self.target else {
+            panic!(
+                "ShaderRunner::set_supersample needs a runner created by `ShaderRunner::headless`"
+            );
+        };
+
+        self.channels
+            .resize(&self.device, factor * width, factor * height);
+        self.uniform.resize(factor * width, factor * height);
+        self.uniform.channel_resolution = self.channels.resolutions();
+
+        self.supersample = factor;
+        self.create_downsample();
+    }
+
+    /// Averages `sub_frames` renders into each recorded frame, spread evenly over the first
+    /// `shutter_angle` degrees of the frame's interval, where 360 is the whole interval.
+    pub fn set_motion_blur(&mut self, sub_frames: u32, shutter_angle: f32) {
+        self.sub_frames = sub_frames.max(1);
+        self.shutter = shutter_angle / 360.;
+        self.create_downsample();
+    }
+
+    /// Rebuilds the passes between the image pass and the target for the current supersampling
+    /// and motion blur: the image is downsampled into the accumulator with the sub-frames'
+    /// weight, which is then copied into the target once all sub-frames are added up.
+    fn create_downsample(&mut self) {
+        let Target::Record { width, height, .. } = self.target else {
+            panic!(
+                "Supersampling and motion blur need a runner created by `ShaderRunner::headless`"
+            );
+        };
+
+        self.accumulator = (self.sub_frames > 1).then(|| {
+            Downsample::new(
+                &self.device,
+                &self.vert_shader,
+                ACCUMULATOR_FORMAT,
+                self.format,
+                width,
+                height,
+                1,
+                None,
+            )
+        });
+
+        let (format, weight) = match self.accumulator {
+            Some(_) => (ACCUMULATOR_FORMAT, Some(1. / self.sub_frames as f32)),
+            None => (self.format, None),
+        };
+
+        self.downsample = (self.supersample > 1 || self.accumulator.is_some()).then(|| {
+            Downsample::new(
+                &self.device,
+                &self.vert_shader,
+                self.format,
+                format,
+                width,
+                height,
+                self.supersample,
+                weight,
+            )
+        });
+    }
+
+    /// Times the frames rendered in the window, see [`WindowState::timings`].
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(Profiler::new(&self.device, &self.queue));
+    }
+
+    /// Saves screenshots into `dir` instead of `images/`, rendered at `size` instead of the
+    /// window's size if given, failing if the device cannot render textures that size.
+    pub fn set_screenshots(
+        &mut self,
+        dir: impl Into<std::path::PathBuf>,
+        size: Option<[u32; 2]>,
+    ) -> Result<(), String> {
+        if let Some([width, height]) = size {
+            let max = self.device.limits().max_texture_dimension_2d;
+
+            if !(1..=max).contains(&width) || !(1..=max).contains(&height) {
+                return Err(format!(
+                    "screenshots must be between 1 and {} pixels wide and high, not {}×{}",
+                    max, width, height
+                ));
+            }
+        }
+
+        self.screenshot_dir = dir.into();
+        self.screenshot_size = size;
+
+        Ok(())
+    }
+
+    /// Renders the last frame again offscreen, with the same uniforms and buffers, and saves it
+    /// as a PNG file named after the current time.
+    ///
+    /// The frame is drawn in the window's format and converted to 8 bit RGBA, whatever format
+    /// the surface ended up with.
+    pub fn save_screenshot(&mut self) -> image::ImageResult<std::path::PathBuf> {
+        let Target::Window { config, .. } = &self.target else {
+            panic!(
+                "ShaderRunner::save_screenshot needs a runner created by `ShaderRunner::windowed`"
+            );
+        };
+
+        let [width, height] = self
+            .screenshot_size
+            .unwrap_or([config.width, config.height]);
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+
+        let texture = self
+            .device
+            .create_texture(&create_texture_desc(width, height, format));
+        let view = texture.create_view(&Default::default());
+
+        let convert = Downsample::new(
+            &self.device,
+            &self.vert_shader,
+            self.format,
+            format,
+            width,
+            height,
+            1,
+            None,
+        );
+        let output_buffer = self
+            .device
+            .create_buffer(&create_output_buffer_desc(width, height, format));
+
+        let window_uniform = self.uniform;
+        self.uniform.resize(width, height);
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+        self.uniform = window_uniform;
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        self.draw_image(&mut encoder, convert.view());
+        convert.draw(&mut encoder, &view, true);
+
+        copy_texture_to_buffer(&mut encoder, &texture, &output_buffer, width, height);
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let [year, month, day, seconds] = date(std::time::SystemTime::now());
+        let seconds = seconds as f64;
+        let name = format!(
+            "{:04}-{:02}-{:02}_{:02}-{:02}-{:06.3}.png",
+            year,
+            month + 1.,
+            day,
+            (seconds / 3600.).floor(),
+            (seconds / 60. % 60.).floor(),
+            seconds % 60.
+        );
+
+        std::fs::create_dir_all(&self.screenshot_dir)?;
+        let path = self.screenshot_dir.join(name);
+
+        pollster::block_on(save_buffer_as_image(
+            &output_buffer,
+            &self.device,
+            width,
+            height,
+            format,
+            &path,
+        ))?;
+
+        Ok(path)
+    }
+
+    /// Records into `output` instead of numbered PNG files in `images/`.
+    pub fn set_output(&mut self, output: Output) {
+        self.output = Some(output);
+    }
+
+    /// Records `frame_rate` frames per second, starting `start_time` seconds in.
+    pub fn set_timing(&mut self, frame_rate: f32, start_time: f32) {
+        self.frame_rate = Some(frame_rate);
+        self.start_time = start_time;
+    }
+
+    /// Renders frame `i` at `width`×`height`, which may be larger than any texture, one tile the
+    /// size of the target at a time, and passes every row of tiles to `write_rows` from the top
+    /// as soon as it is stitched together.
+    ///
+    /// Tiles are moved into place by [`Uniform::offset`], so shaders that ignore it render the
+    /// same tile over and over. Buffers cannot be tiled.
+    pub fn capture_tiled(
+        &mut self,
+        i: i32,
+        width: u32,
+        height: u32,
+        mut write_rows: impl FnMut(image::RgbaImage),
+    ) {
+        assert!(
+            self.channels.buffers_mut().next().is_none(),
+            "Buffers cannot be rendered in tiles"
+        );
+
+        let Target::Record {
+            width: tile_width,
+            height: tile_height,
+            ..
+        } = self.target
+        else {
+            panic!(
+                "ShaderRunner::capture_tiled needs a runner created by `ShaderRunner::headless`"
+            );
+        };
+        let factor = self.supersample;
+
+        self.uniform.resize(factor * width, factor * height);
+
+        for y in (0..height).step_by(tile_height as usize) {
+            let mut rows = image::RgbaImage::new(width, tile_height.min(height - y));
+
+            for x in (0..width).step_by(tile_width as usize) {
+                self.uniform.offset = [(factor * x) as f32, (factor * y) as f32];
+
+                // Every tile after the first renders the same frame again, with the same mouse
+                // and keyboard state.
+                let tile = self.read_back(i, x == 0 && y == 0).into_rgba8();
+
+                // Clips the tiles on the right and bottom edges.
+                image::imageops::replace(&mut rows, &tile, x, 0);
+            }
+
+            write_rows(rows);
+        }
+
+        self.uniform.offset = [0.; 2];
+        self.uniform
+            .resize(factor * tile_width, factor * tile_height);
+    }
+
+    /// Renders frame `i` offscreen and reads it back with 8 bits per channel, see
+    /// [`ShaderRunner::capture_frame`].
+    pub fn capture(&mut self, i: i32) -> image::RgbaImage {
+        self.capture_frame(i).into_rgba8()
+    }
+
+    /// Renders frame `i` offscreen and reads it back in the precision it was rendered at, after
+    /// writing the frames still being read back for [`RecordState::record`].
+    pub fn capture_frame(&mut self, i: i32) -> Frame {
+        self.read_back(i, true)
+    }
+
+    fn read_back(&mut self, i: i32, advance: bool) -> Frame {
+        self.flush();
+        self.submit(i, advance);
+
+        self.receive().unwrap().1
+    }
+
+    /// Renders frame `i` offscreen and starts copying it into the next output buffer, first
+    /// reading back the oldest frame if no buffer is free.
+    ///
+    /// The mouse and keyboard move on to the frame unless `advance` is `false`, which renders
+    /// the last frame again.
+    fn submit(&mut self, i: i32, advance: bool) -> Option<(i32, Frame)> {
+        let received = if self.readbacks.len() == READBACK_BUFFERS {
+            self.receive()
+        } else {
+            None
+        };
+
+        let frame_rate = self.frame_rate.unwrap_or(FRAME_RATE);
+
+        let buffer = self
+            .readbacks
+            .back()
+            .map_or(0, |readback| (readback.buffer + 1) % READBACK_BUFFERS);
+
+        // Each sub-frame is submitted on its own, since the uniforms it is drawn with are only
+        // written once submitted.
+        let mut submission = None;
+
+        for sub_frame in 0..self.sub_frames {
+            let offset = self.shutter * sub_frame as f32 / self.sub_frames as f32;
+
+            let time = self.start_time + (i as f32 + offset) / frame_rate;
+
+            // Sub-frames after the first only move time along within the frame.
+            if sub_frame == 0 && advance {
+                self.update(time, i);
+            } else {
+                self.update_time(time);
+            }
+
+            let Target::Record {
+                texture,
+                texture_view,
+                output_buffers,
+                width,
+                height,
+            } = &self.target
+            else {
+                panic!("Rendering offscreen needs a runner created by `ShaderRunner::headless`");
+            };
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+            self.draw(&mut encoder, texture_view, sub_frame);
+
+            if sub_frame + 1 == self.sub_frames {
+                let output_buffer = &output_buffers[buffer];
+
+                copy_texture_to_buffer(&mut encoder, texture, output_buffer, *width, *height);
+            }
+
+            submission = Some(self.queue.submit(Some(encoder.finish())));
+        }
+
+        let Target::Record { output_buffers, .. } = &self.target else {
+            panic!("Rendering offscreen needs a runner created by `ShaderRunner::headless`");
+        };
+        let output_buffer = &output_buffers[buffer];
+        let submission = submission.unwrap();
+
+        let (sender, mapped) = flume::bounded(1);
+
+        output_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+
+        self.readbacks.push_back(Readback {
+            frame: i,
+            buffer,
+            submission,
+            mapped,
+        });
+
+        received
+    }
+
+    /// Waits for the oldest frame being read back and returns it.
+    fn receive(&mut self) -> Option<(i32, Frame)> {
+        let readback = self.readbacks.pop_front()?;
+
+        let Target::Record {
+            texture,
+            output_buffers,
+            width,
+            height,
+            ..
+        } = &self.target
+        else {
+            panic!("Reading back frames needs a runner created by `ShaderRunner::headless`");
+        };
+
+        self.device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(readback.submission));
+        readback.mapped.recv().unwrap().unwrap();
+
+        let frame = read_frame(
+            &output_buffers[readback.buffer],
+            *width,
+            *height,
+            texture.format(),
+        )
+        .unwrap_or_else(|err| panic!("Failed to read back frame {}: {}", readback.frame, err));
+
+        Some((readback.frame, frame))
+    }
+
+    /// Writes frame `i` to the output, numbered PNG files in `images/` unless set otherwise.
+    fn write(&mut self, i: i32, frame: Frame) {
+        let frame_rate = self.frame_rate.unwrap_or(FRAME_RATE);
+
+        let output = self.output.get_or_insert_with(|| {
+            Output::images("images", ImageFormat::Png)
+                .unwrap_or_else(|err| panic!("Failed to create images: {}", err))
+        });
+
+        output
+            .write(i, frame, frame_rate)
+            .unwrap_or_else(|err| panic!("Failed to write frame {}: {}", i, err));
+    }
+
+    /// Writes all frames still being read back.
+    fn flush(&mut self) {
+        while let Some((i, frame)) = self.receive() {
+            self.write(i, frame);
+        }
+    }
+
+    /// Moves on to `frame`, which starts at `time`.
+    fn update(&mut self, time: f32, frame: i32) {
+        self.uniform.time_delta = match self.frame_rate {
+            Some(frame_rate) => 1. / frame_rate,
+            None if frame > 0 => time - self.uniform.time,
+            None => 0.,
+        };
+        self.uniform.frame = frame;
+
+        // Measured in real time, which playback speed and pausing leave alone.
+        let updated = std::time::Instant::now();
+        let elapsed = (updated - self.updated).as_secs_f32();
+        self.updated = updated;
+
+        self.uniform.frame_rate = match self.frame_rate {
+            Some(frame_rate) => frame_rate,
+            None if elapsed > 0. => {
+                let frame_rate = 1. / elapsed;
+
+                if self.uniform.frame_rate > 0. {
+                    0.9 * self.uniform.frame_rate + 0.1 * frame_rate
+                } else {
+                    frame_rate
+                }
+            }
+            None => self.uniform.frame_rate,
+        };
+        self.uniform.mouse = self.mouse.next_frame();
+        self.channels.keyboard_mut().write(&self.queue);
+
+        self.update_time(time);
+    }
+
+    /// Moves to `time` within the current frame, as motion blur's sub-frames do.
+    fn update_time(&mut self, time: f32) {
+        self.uniform.time = time;
+        self.uniform.date = date(self.started + std::time::Duration::from_secs_f32(time));
+
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+    }
+
+    /// Draws the frame into `view`, or with motion blur, adds `sub_frame` to the accumulator and
+    /// resolves it into `view` after the last one.
+    fn draw(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, sub_frame: u32) {
+        let frame = self.uniform.frame as usize;
+
+        assert!(
+            self.params_read() <= self.params_buffer.size(),
+            "The shaders read {} bytes of parameters but only {} are bound, declare them in a \
+             parameter file",
+            self.params_read(),
+            self.params_buffer.size()
+        );
+
+        self.channels.draw(encoder, frame, &self.uniform_bind_group);
+
+        let image_view = match &self.downsample {
+            Some(downsample) => downsample.view(),
+            None => view,
+        };
+
+        self.draw_image(encoder, image_view);
+
+        match (&self.downsample, &self.accumulator) {
+            (Some(downsample), Some(accumulator)) => {
+                downsample.draw(encoder, accumulator.view(), sub_frame == 0);
+
+                if sub_frame + 1 == self.sub_frames {
+                    accumulator.draw(encoder, view, true);
+                }
+            }
+            (Some(downsample), None) => downsample.draw(encoder, view, true),
+            _ => {}
+        }
+    }
+
+    /// Draws the image pass alone, reading the buffers drawn for the current frame.
+    fn draw_image(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let frame = self.uniform.frame as usize;
+
+        let mut render_pass = create_render_pass(encoder, view);
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        render_pass.set_bind_group(1, self.channels.image_bind_group(frame), &[]);
+
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Builds a pipeline from `shader_file` if it changed, along with the bytes of parameters it
+/// reads, logging why when it cannot, e.g. because it reads more than the `bound` bytes.
+fn reload_pipeline(
+    device: &wgpu::Device,
+    render_pipeline_layout: &wgpu::PipelineLayout,
+    vert_shader: &wgpu::ShaderModule,
+    shader_file: &mut ShaderFile,
+    format: wgpu::TextureFormat,
+    bound: u64,
+) -> Option<(wgpu::RenderPipeline, u64)> {
+    if !shader_file.changed() {
+        return None;
+    }
+
+    let frag_shader_desc = match shader_file.load() {
+        Ok(frag_shader_desc) => frag_shader_desc,
+        Err(err) => {
+            log::error!("Failed to read {}: {}", shader_file.path().display(), err);
+            return None;
+        }
+    };
+
+    // Parsed here rather than by wgpu, which panics on GLSL that fails to parse.
+    let frag_entry_point = fragment_entry_point(&frag_shader_desc.source);
+    let (frag_shader_desc, module) = match shader::parse(frag_shader_desc) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            log::error!(
+                "Failed to reload {}:\n{}",
+                shader_file.path().display(),
+                err
+            );
+            return None;
+        }
+    };
+
+    let params_size = params_size(&module);
+
+    if params_size > bound {
+        log::error!(
+            "Failed to reload {}: it reads {} bytes of parameters but only {} are bound",
+            shader_file.path().display(),
+            params_size,
+            bound
+        );
+        return None;
+    }
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let frag_shader = device.create_shader_module(frag_shader_desc);
+    let render_pipeline = create_render_pipeline(
+        device,
+        render_pipeline_layout,
+        vert_shader,
+        &frag_shader,
+        frag_entry_point,
+        &[],
+        format,
+        1,
+    );
+
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(err) => {
+            log::error!("Failed to reload {}: {}", shader_file.path().display(), err);
+            None
+        }
+        None => {
+            log::info!("Reloaded {}", shader_file.path().display());
+            Some((render_pipeline, params_size))
+        }
+    }
+}
+
+impl WindowState for ShaderRunner {
+    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        let Target::Window {
+            surface, config, ..
+        } = &mut self.target
+        else {
+            panic!("WindowState::resize needs a runner created by `ShaderRunner::windowed`");
+        };
+
+        if new_size.width > 0 && new_size.height > 0 {
+            config.width = new_size.width;
+            config.height = new_size.height;
+            self.uniform.resize(new_size.width, new_size.height);
+            surface.configure(&self.device, config);
+
+            self.channels
+                .resize(&self.device, new_size.width, new_size.height);
+            self.uniform.channel_resolution = self.channels.resolutions();
+        }
+    }
+
+    fn cursor_moved(&mut self, position: winit::dpi::PhysicalPosition<f64>) {
+        self.mouse.moved(position.x as f32, position.y as f32);
+    }
+
+    fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        match (state, button) {
+            (ElementState::Pressed, MouseButton::Left) => self.mouse.pressed(),
+            (ElementState::Released, MouseButton::Left) => self.mouse.released(),
+            _ => (),
+        }
+    }
+
+    fn keyboard_input(&mut self, event: &KeyEvent) {
+        if let PhysicalKey::Code(key) = event.physical_key {
+            self.channels
+                .keyboard_mut()
+                .input(key, event.state == ElementState::Pressed);
+        }
+    }
+
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        self.uniform.pixel_ratio = scale_factor as f32;
+    }
+
+    fn render(&mut self, time: f32, frame: i32) -> Result<(), wgpu::SurfaceError> {
+        self.reload();
+
+        let Target::Window { surface, .. } = &self.target else {
+            panic!("WindowState::render needs a runner created by `ShaderRunner::windowed`");
+        };
+
+        let output = surface.get_current_texture()?;
+
+        self.update(time, frame);
+
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.format),
+            ..Default::default()
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin(&mut encoder);
+        }
+
+        self.draw(&mut encoder, &view, 0);
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end(&mut encoder);
+        }
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        if let Some(timings) = self
+            .profiler
+            .as_mut()
+            .and_then(|profiler| profiler.submitted(&self.device))
+        {
+            self.timings = Some(timings);
+        }
+
+        self.channels.keyboard_mut().end_frame();
+
+        Ok(())
+    }
+
+    fn screenshot(&mut self) {
+        match self.save_screenshot() {
+            Ok(path) => log::info!("Saved {}", path.display()),
+            Err(err) => log::error!("Failed to save screenshot: {}", err),
+        }
+    }
+
+    fn timings(&mut self) -> Option<String> {
+        self.timings.take()
+    }
+}
+
+impl RecordState for ShaderRunner {
+    fn record(&mut self, i: i32) {
+        if let Some((i, frame)) = self.submit(i, true) {
+            self.write(i, frame);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.flush();
+
+        if let Some(output) = self.output.take() {
+            output
+                .finish()
+                .unwrap_or_else(|err| panic!("Failed to finish recording: {}", err));
+        }
+    }
+}
+
+/// Runs `frag_shader_desc`, or the file given by `--shader`, according to `options`, either in
+/// a window or by recording frames.
+pub fn run(frag_shader_desc: wgpu::ShaderModuleDescriptor<'_>, options: &Options) {
+    options.validate().unwrap_or_else(|err| err.exit());
+
+    let logger = SimpleLogger::new();
+
+    if options.verbose {
+        logger.init().unwrap();
+    } else {
+        logger.with_level(log::LevelFilter::Error).init().unwrap();
+    }
+
+    let shader_file = options
+        .shader
+        .as_ref()
+        .map(|path| ShaderFile::new(path, options.lang));
+
+    let frag_shader_desc = match &shader_file {
+        Some(shader_file) => shader_file.load().unwrap_or_else(|err| {
+            panic!("Failed to read {}: {}", shader_file.path().display(), err)
+        }),
+        None => frag_shader_desc,
+    };
+
+    if let Some(tile) = options.tile {
+        // Shadertoy shaders get the offset added to `fragCoord`, others must read it themselves.
+        if !is_shadertoy(&frag_shader_desc.source) {
+            log::error!(
+                "Tiling a shader that is not a Shadertoy shader, which must add `offset` from \
+                 the uniforms to its fragment position, or every tile shows the same pixels"
+            );
+        }
+
+        let mut state = pollster::block_on(ShaderRunner::headless(
+            frag_shader_desc,
+            tile.min(options.width),
+            tile.min(options.height),
+            Precision::U8,
+        ));
+
+        state.set_supersample(options.supersample);
+        state.set_motion_blur(options.motion_blur, options.shutter_angle);
+
+        add_channels(&mut state, options);
+
+        if let Some([x, y]) = options.mouse.as_deref() {
+            state.hold_mouse(*x, *y);
+        }
+
+        state.set_timing(options.fps, options.start_time);
+
+        let path = match &options.output {
+            Some(path) => path.clone(),
+            None => {
+                std::fs::create_dir_all(&options.output_dir).unwrap_or_else(|err| {
+                    panic!("Failed to create {}: {}", options.output_dir.display(), err)
+                });
+
+                options.output_dir.join(format!("{:0>8}.png", 0))
+            }
+        };
+
+        let mut png = PngRows::create(&path, options.width, options.height)
+            .unwrap_or_else(|err| panic!("Failed to create {}: {}", path.display(), err));
+
+        state.capture_tiled(0, options.width, options.height, |rows| {
+            png.write(&rows)
+                .unwrap_or_else(|err| panic!("Failed to write {}: {}", path.display(), err));
+        });
+
+        png.finish()
+            .unwrap_or_else(|err| panic!("Failed to write {}: {}", path.display(), err));
+    } else if options.record {
+        let mut state = pollster::block_on(ShaderRunner::headless(
+            frag_shader_desc,
+            options.width,
+            options.height,
+            options.precision(),
+        ));
+
+        state.set_supersample(options.supersample);
+        state.set_motion_blur(options.motion_blur, options.shutter_angle);
+
+        add_channels(&mut state, options);
+
+        if let Some([x, y]) = options.mouse.as_deref() {
+            state.hold_mouse(*x, *y);
+        }
+
+        state.set_timing(options.fps, options.start_time);
+
+        let output = match &options.output {
+            Some(path) => Output::create(
+                path,
+                options.format,
+                options.width,
+                options.height,
+                options.frames(),
+                options.fps,
+            ),
+            None => Output::images(&options.output_dir, options.image_format),
+        };
+        let path = options.output.as_ref().unwrap_or(&options.output_dir);
+
+        state.set_output(
+            output.unwrap_or_else(|err| panic!("Failed to create {}: {}", path.display(), err)),
+        );
+
+        record(state, options.frames() as i32);
+    } else {
+        let event_loop = create_event_loop().unwrap();
+        let window = create_window(options.width, options.height, &event_loop).unwrap();
+
+        let mut state = pollster::block_on(ShaderRunner::windowed(
+            &window,
+            frag_shader_desc,
+            options.present_mode,
+        ));
+
+        if let Some(shader_file) = shader_file {
+            state.watch(shader_file);
+        }
+
+        add_channels(&mut state, options);
+
+        if options.timings {
+            state.enable_profiler();
+        }
+
+        let mut fullscreen =
+            Fullscreen::new(options.fullscreen.unwrap_or_default(), options.monitor);
+        fullscreen.place(&window);
+
+        if options.fullscreen.is_some() {
+            fullscreen.enter(&window);
+        }
+
+        let screenshot_size = match options.screenshot_size.as_deref() {
+            Some(&[width, height]) => Some([width, height]),
+            _ => None,
+        };
+        state
+            .set_screenshots(&options.output_dir, screenshot_size)
+            .unwrap_or_else(|err| {
+                Options::command()
+                    .error(
+                        clap::error::ErrorKind::ValueValidation,
+                        format!("invalid value for `--screenshot-size`: {}", err),
+                    )
+                    .exit()
+            });
+
+        let _ = render(
+            event_loop,
+            window,
+            fullscreen,
+            Clock::new(options.fps),
+            state,
+        );
+    }
+}
+
+/// Binds the parameters given by `--params`, or the ones next to `--shader`, adds the buffers
+/// given by `--buffer` and binds the channels given by `--channel`.
+fn add_channels(state: &mut ShaderRunner, options: &Options) {
+    let params_file = match (&options.params, &options.shader) {
+        (Some(path), _) => Some(ParamsFile::new(path)),
+        (None, Some(path)) => Some(ParamsFile::sidecar(path)).filter(ParamsFile::exists),
+        (None, None) => None,
+    };
+
+    if let Some(params_file) = params_file {
+        let path = params_file.path().to_owned();
+
+        state
+            .set_params(params_file)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+    }
+
+    for path in &options.buffers {
+        state.add_buffer(ShaderFile::new(path, options.lang).buffer());
+    }
+
+    if !options.channels.is_empty() {
+        state
+            .set_channels(options.channels.clone())
+            .unwrap_or_else(|err| panic!("Failed to load channels: {}", err));
+    }
+}
+
+/// Appends the non-empty `parts` to `title`.
+fn window_title(title: &str, parts: &[&str]) -> String {
+    std::iter::once(title)
+        .chain(parts.iter().copied().filter(|part| !part.is_empty()))
+        .collect::<Vec<_>>()
+        .join(" - ")
+}
+
+pub fn render(
+    event_loop: EventLoop<()>,
+    window: Window,
+    mut fullscreen: Fullscreen,
+    mut clock: Clock,
+    mut state: impl WindowState,
+) -> Result<(), EventLoopError> {
+    let state_window_id = window.id();
+    let title = window.title();
+    let mut timings = String::new();
+
+    event_loop.run(move |event, elwt| match event {
+        Event::WindowEvent { event, window_id } if window_id == state_window_id => match event {
+            WindowEvent::CloseRequested => elwt.exit(),
+            WindowEvent::Resized(physical_size) => state.resize(physical_size),
+            WindowEvent::CursorMoved { position, .. } => state.cursor_moved(position),
+            WindowEvent::MouseInput {
+                state: button_state,
+                button,
+                ..
+            } => state.mouse_input(button_state, button),
+            WindowEvent::KeyboardInput { event, .. } => {
+                if event.state == ElementState::Pressed && !event.repeat {
+                    match event.physical_key {
+                        PhysicalKey::Code(KeyCode::F11) => fullscreen.toggle(&window),
+                        PhysicalKey::Code(KeyCode::F12) => state.screenshot(),
+                        _ => {}
+                    }
+                }
+
+                if clock.input(&event) {
+                    window.set_title(&window_title(&title, &[&clock.status(), &timings]));
+                } else {
+                    state.keyboard_input(&event);
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => clock.modifiers_changed(&modifiers),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                state.scale_factor_changed(scale_factor)
+            }
+            WindowEvent::RedrawRequested => {
+                let (time, frame) = clock.tick();
+
+                match state.render(time, frame) {
+                    Ok(_) => {}
+                    _ => elwt.exit(),
+                }
+
+                if let Some(report) = state.timings() {
+                    log::info!("{}", report);
+
+                    timings = report;
+                    window.set_title(&window_title(&title, &[&clock.status(), &timings]));
+                }
+            }
+            _ => (),
+        },
+
+        Event::AboutToWait => window.request_redraw(),
+        _ => (),
+    })
+}
+
+pub fn record(mut state: impl RecordState, frames: i32) {
+    for i in 0..frames {
+        state.record(i);
+    }
+
+    state.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn date_at(seconds: f64) -> [f32; 4] {
+        date(UNIX_EPOCH + Duration::from_secs_f64(seconds))
+    }
+
+    #[test]
+    fn date_counts_months_from_zero() {
+        assert_eq!(date_at(0.), [1970., 0., 1., 0.]);
+        assert_eq!(date_at(1704067199.), [2023., 11., 31., 86399.]);
+        assert_eq!(date_at(951868801.), [2000., 2., 1., 1.]);
+    }
+
+    #[test]
+    fn date_handles_leap_days() {
+        assert_eq!(date_at(1709209815.5), [2024., 1., 29., 45015.5]);
+    }
+
+    #[test]
+    fn timing_is_validated() {
+        let parse = |args: &[&str]| Options::try_parse_from([&["shader-rs"], args].concat());
+
+        assert!(parse(&["--fps", "0"]).is_err());
+        assert!(parse(&["--fps", "NaN"]).is_err());
+        assert!(parse(&["--start-time=-1"]).is_err());
+        assert!(parse(&["--duration=-5"]).is_err());
+        assert!(parse(&["--duration", "NaN"]).is_err());
+        assert!(parse(&["--duration", "0"]).is_err());
+
+        let options = parse(&["--fps", "29.97", "--start-time", "0"]).unwrap();
+
+        assert_eq!((options.fps, options.start_time), (29.97, 0.));
+    }
+
+    #[test]
+    fn screenshot_size_is_validated() {
+        let parse = |size: [&str; 2]| {
+            Options::try_parse_from([&["shader-rs", "--screenshot-size"][..], &size].concat())
+        };
+
+        assert!(parse(["0", "512"]).is_err());
+
+        let options = parse(["3840", "2160"]).unwrap();
+
+        assert_eq!(options.screenshot_size, Some(vec![3840, 2160]));
+    }
+
+    #[test]
+    fn shutter_angle_is_validated() {
+        let parse = |angle: &str| {
+            Options::try_parse_from(["shader-rs", &format!("--shutter-angle={}", angle)])
+        };
+
+        for angle in ["0", "-90", "361", "NaN", "inf"] {
+            assert!(parse(angle).is_err(), "{}", angle);
+        }
+
+        assert_eq!(parse("360").unwrap().shutter_angle, 360.);
+    }
+
+    #[test]
+    fn tiles_conflict_with_buffers() {
+        let parse = |args: &[&str]| Options::try_parse_from([&["shader-rs"], args].concat());
+
+        assert!(parse(&["--record", "--tile", "256", "--buffer", "a.glsl"]).is_err());
+        assert!(parse(&["--record", "--tile", "256"]).is_ok());
+    }
+
+    #[test]
+    fn sample_count_is_supported() {
+        let features = wgpu::Features::empty();
+        let rgba8 = wgpu::TextureFormat::Rgba8Unorm.guaranteed_format_features(features);
+        let rgba32 = wgpu::TextureFormat::Rgba32Float.guaranteed_format_features(features);
+
+        assert_eq!(max_sample_count(rgba8.flags, 8), 4);
+        assert_eq!(max_sample_count(rgba8.flags, 3), 1);
+        assert_eq!(max_sample_count(rgba32.flags, 4), 1);
+
+        let flags = wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X2
+            | wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X8;
+
+        assert_eq!(max_sample_count(flags, 16), 8);
+        assert_eq!(max_sample_count(flags, 4), 2);
     }
 }
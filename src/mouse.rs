@@ -0,0 +1,46 @@
+/// Tracks the left mouse button the way Shadertoy's `iMouse` reports it.
+#[derive(Default)]
+pub(crate) struct Mouse {
+    position: [f32; 2],
+    down: bool,
+    clicked: bool,
+
+    drag: [f32; 2],
+    click: [f32; 2],
+}
+
+impl Mouse {
+    pub fn moved(&mut self, x: f32, y: f32) {
+        self.position = [x, y];
+
+        if self.down {
+            self.drag = self.position;
+        }
+    }
+
+    pub fn pressed(&mut self) {
+        self.down = true;
+        self.clicked = true;
+
+        self.drag = self.position;
+        self.click = self.position;
+    }
+
+    pub fn released(&mut self) {
+        self.down = false;
+    }
+
+    /// Returns the value for `Uniform::mouse` on the next frame: the position while dragging,
+    /// and the last click position with `z` negated once the button is released and `w` negated
+    /// after the frame it was clicked on.
+    pub fn next_frame(&mut self) -> [f32; 4] {
+        let [x, y] = self.click;
+
+        let z = if self.down { x } else { -x };
+        let w = if self.clicked { y } else { -y };
+
+        self.clicked = false;
+
+        [self.drag[0], self.drag[1], z, w]
+    }
+}
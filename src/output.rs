@@ -0,0 +1,606 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+
+type Writer = BufWriter<Box<dyn Write + Send>>;
+
+pub type Rgba32FImage = image::ImageBuffer<image::Rgba<f32>, Vec<f32>>;
+
+/// Precision of the texture recordings are rendered into.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// 8 bits per channel, clamped to [0, 1]
+    U8,
+    /// Half floats
+    F16,
+    /// Floats
+    F32,
+}
+
+impl Precision {
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            Precision::U8 => wgpu::TextureFormat::Rgba8Unorm,
+            Precision::F16 => wgpu::TextureFormat::Rgba16Float,
+            Precision::F32 => wgpu::TextureFormat::Rgba32Float,
+        }
+    }
+}
+
+/// A recorded frame, read back in the precision it was rendered at.
+pub enum Frame {
+    Rgba8(image::RgbaImage),
+    Rgba32F(Rgba32FImage),
+}
+
+impl Frame {
+    /// Converts rows of pixels in `format`, which are `bytes_per_row` apart in `data`, failing
+    /// for formats other than 8 bit RGBA or BGRA and 16 or 32 bit float RGBA.
+    pub fn from_bytes(
+        data: &[u8],
+        bytes_per_row: usize,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> image::ImageResult<Self> {
+        let Some(block_size) = format.block_size(None) else {
+            return Err(unsupported(format));
+        };
+        let block_size = block_size as usize;
+
+        let rows = data
+            .chunks_exact(bytes_per_row)
+            .take(height as usize)
+            .map(|row| &row[..block_size * width as usize]);
+
+        Ok(match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                let pixels = rows.flatten().copied().collect();
+
+                Frame::Rgba8(image::RgbaImage::from_raw(width, height, pixels).unwrap())
+            }
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                let pixels = rows
+                    .flat_map(|row| row.chunks_exact(4))
+                    .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                    .collect();
+
+                Frame::Rgba8(image::RgbaImage::from_raw(width, height, pixels).unwrap())
+            }
+            wgpu::TextureFormat::Rgba16Float => {
+                let pixels = rows
+                    .flat_map(|row| row.chunks_exact(2))
+                    .map(|half| f16_to_f32(u16::from_ne_bytes([half[0], half[1]])))
+                    .collect();
+
+                Frame::Rgba32F(Rgba32FImage::from_raw(width, height, pixels).unwrap())
+            }
+            wgpu::TextureFormat::Rgba32Float => {
+                let pixels = rows
+                    .flat_map(|row| row.chunks_exact(4))
+                    .map(|float| f32::from_ne_bytes([float[0], float[1], float[2], float[3]]))
+                    .collect();
+
+                Frame::Rgba32F(Rgba32FImage::from_raw(width, height, pixels).unwrap())
+            }
+            _ => return Err(unsupported(format)),
+        })
+    }
+
+    /// Returns the frame with 8 bits per channel, clamping float values to [0, 1].
+    pub fn into_rgba8(self) -> image::RgbaImage {
+        match self {
+            Frame::Rgba8(frame) => frame,
+            Frame::Rgba32F(frame) => image::RgbaImage::from_raw(
+                frame.width(),
+                frame.height(),
+                frame
+                    .into_raw()
+                    .into_iter()
+                    .map(|value| (value.clamp(0., 1.) * 255.).round() as u8)
+                    .collect(),
+            )
+            .unwrap(),
+        }
+    }
+
+    /// Returns the frame as floats, mapping 8 bit channels to [0, 1].
+    pub fn into_rgba32f(self) -> Rgba32FImage {
+        match self {
+            Frame::Rgba8(frame) => Rgba32FImage::from_raw(
+                frame.width(),
+                frame.height(),
+                frame
+                    .into_raw()
+                    .into_iter()
+                    .map(|value| value as f32 / 255.)
+                    .collect(),
+            )
+            .unwrap(),
+            Frame::Rgba32F(frame) => frame,
+        }
+    }
+}
+
+fn unsupported(format: wgpu::TextureFormat) -> image::ImageError {
+    image::ImageError::Unsupported(image::error::UnsupportedError::from_format_and_kind(
+        image::error::ImageFormatHint::Unknown,
+        image::error::UnsupportedErrorKind::GenericFeature(format!("reading back {:?}", format)),
+    ))
+}
+
+/// Converts an IEEE 754 half float, as stored in `Rgba16Float` textures.
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1. } else { 1. };
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = (half & 0x3ff) as f32;
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0. => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1. + mantissa / 1024.) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+/// File formats of numbered frames.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// PNG, with 8 bits per channel
+    Png,
+    /// OpenEXR, with float channels
+    Exr,
+    /// Radiance HDR, with float channels but no alpha
+    Hdr,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Exr => "exr",
+            ImageFormat::Hdr => "hdr",
+        }
+    }
+
+    fn save(self, frame: Frame, path: &Path) -> io::Result<()> {
+        match self {
+            ImageFormat::Png => frame.into_rgba8().save(path).map_err(io::Error::other),
+            ImageFormat::Exr => {
+                let frame = frame.into_rgba32f();
+
+                exr::prelude::write_rgba_file(
+                    path,
+                    frame.width() as usize,
+                    frame.height() as usize,
+                    |x, y| frame.get_pixel(x as u32, y as u32).0.into(),
+                )
+                .map_err(io::Error::other)
+            }
+            ImageFormat::Hdr => {
+                let frame = frame.into_rgba32f();
+                let pixels: Vec<_> = frame
+                    .pixels()
+                    .map(|&image::Rgba([r, g, b, _])| image::Rgb([r, g, b]))
+                    .collect();
+
+                image::codecs::hdr::HdrEncoder::new(BufWriter::new(File::create(path)?))
+                    .encode(&pixels, frame.width() as usize, frame.height() as usize)
+                    .map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+/// File formats recordings can be written in besides numbered PNG files.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Animated GIF
+    Gif,
+    /// Animated PNG
+    Apng,
+    /// YUV4MPEG2 with 4:4:4 chroma, which encoders like ffmpeg read from a pipe
+    Y4m,
+    /// Headerless RGBA frames
+    Raw,
+}
+
+impl Format {
+    /// Guesses the format from the extension of `path`.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        Some(match extension.as_str() {
+            "gif" => Format::Gif,
+            "png" | "apng" => Format::Apng,
+            "y4m" => Format::Y4m,
+            "rgba" | "raw" => Format::Raw,
+            _ => return None,
+        })
+    }
+}
+
+/// Where recorded frames are written.
+pub enum Output {
+    /// Numbered files in a directory, e.g. PNG files for `video.sh`, encoded on a thread per
+    /// core.
+    Images {
+        dir: PathBuf,
+        format: ImageFormat,
+        sender: flume::Sender<(PathBuf, Frame)>,
+        workers: Vec<JoinHandle<io::Result<()>>>,
+    },
+    /// An animated GIF, quantized to a palette per frame.
+    Gif(GifEncoder<Writer>),
+    /// An animated PNG, which must receive exactly the number of frames it was created with.
+    Apng(png::Writer<Writer>),
+    /// A YUV4MPEG2 stream, whose header gives the size and frame rate.
+    Y4m(Writer),
+    /// Frames of `width * height * 4` bytes one after another.
+    Raw(Writer),
+}
+
+impl Output {
+    /// Writes numbered files in `format` into `dir`, creating it if missing.
+    pub fn images(dir: impl Into<PathBuf>, format: ImageFormat) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        // Bounded so that rendering waits for the encoders instead of piling up frames.
+        let (sender, receiver) = flume::bounded::<(PathBuf, Frame)>(2 * threads);
+
+        let workers = (0..threads)
+            .map(|_| {
+                let receiver = receiver.clone();
+
+                std::thread::spawn(move || {
+                    for (path, frame) in receiver {
+                        format.save(frame, &path)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .collect();
+
+        Ok(Output::Images {
+            dir,
+            format,
+            sender,
+            workers,
+        })
+    }
+
+    /// Creates an animation at `path`, or on stdout for `-`, looping forever at `frame_rate`.
+    ///
+    /// The format is guessed from the extension unless `format` is given, which is needed for
+    /// stdout and named pipes.
+    pub fn create(
+        path: &Path,
+        format: Option<Format>,
+        width: u32,
+        height: u32,
+        frames: u32,
+        frame_rate: f32,
+    ) -> io::Result<Self> {
+        let format = format.or_else(|| Format::detect(path)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unknown extension, expected .gif, .png, .apng, .y4m or .rgba",
+            )
+        })?;
+
+        let mut writer = open(path)?;
+
+        let output = match format {
+            Format::Gif => {
+                let mut encoder = GifEncoder::new(writer);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(io::Error::other)?;
+
+                Output::Gif(encoder)
+            }
+            Format::Apng => {
+                let mut encoder = png::Encoder::new(writer, width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(frames, 0)?;
+
+                // The delay is the inverse of the frame rate, in milliseconds if the exact
+                // fraction does not fit.
+                let (numerator, denominator) = ratio(frame_rate);
+
+                match (u16::try_from(denominator), u16::try_from(numerator)) {
+                    (Ok(seconds), Ok(frames)) => encoder.set_frame_delay(seconds, frames)?,
+                    _ => encoder.set_frame_delay((1000. / frame_rate).round() as u16, 1000)?,
+                }
+
+                Output::Apng(encoder.write_header()?)
+            }
+            Format::Y4m => {
+                let (numerator, denominator) = ratio(frame_rate);
+
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444",
+                    width, height, numerator, denominator
+                )?;
+
+                Output::Y4m(writer)
+            }
+            Format::Raw => {
+                log::info!(
+                    "Writing raw frames, e.g. for `ffmpeg -f rawvideo -pix_fmt rgba -s {}x{} -r {} -i -`",
+                    width,
+                    height,
+                    frame_rate
+                );
+
+                Output::Raw(writer)
+            }
+        };
+
+        Ok(output)
+    }
+
+    /// Writes frame `i`, shown for `1 / frame_rate` seconds.
+    ///
+    /// Float frames are clamped to 8 bits per channel except in EXR and HDR files.
+    pub fn write(&mut self, i: i32, frame: Frame, frame_rate: f32) -> io::Result<()> {
+        match self {
+            Output::Images {
+                dir,
+                format,
+                sender,
+                ..
+            } => sender
+                .send((dir.join(format!("{:0>8}.{}", i, format.extension())), frame))
+                // The workers only stop early on errors, which `finish` returns.
+                .map_err(|_| io::Error::other("image encoders stopped")),
+            Output::Gif(encoder) => {
+                let delay = std::time::Duration::from_secs_f32(1. / frame_rate);
+                let frame = image::Frame::from_parts(
+                    frame.into_rgba8(),
+                    0,
+                    0,
+                    image::Delay::from_saturating_duration(delay),
+                );
+
+                encoder.encode_frame(frame).map_err(io::Error::other)
+            }
+            Output::Apng(writer) => Ok(writer.write_image_data(&frame.into_rgba8())?),
+            Output::Y4m(writer) => {
+                writer.write_all(b"FRAME\n")?;
+                writer.write_all(&yuv444(&frame.into_rgba8()))
+            }
+            Output::Raw(writer) => writer.write_all(&frame.into_rgba8()),
+        }
+    }
+
+    /// Writes whatever trails the last frame.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Images {
+                sender, workers, ..
+            } => {
+                drop(sender);
+
+                workers
+                    .into_iter()
+                    .try_for_each(|worker| worker.join().unwrap())
+            }
+            // The trailer is written when the encoder is dropped.
+            Output::Gif(encoder) => {
+                drop(encoder);
+                Ok(())
+            }
+            Output::Apng(writer) => Ok(writer.finish()?),
+            Output::Y4m(mut writer) | Output::Raw(mut writer) => writer.flush(),
+        }
+    }
+}
+
+/// A PNG written a band of rows at a time, for stills too large to hold in memory at once.
+pub struct PngRows(png::StreamWriter<'static, Writer>);
+
+impl PngRows {
+    /// Creates a `width`×`height` PNG at `path`, or on stdout for `-`.
+    pub fn create(path: &Path, width: u32, height: u32) -> io::Result<Self> {
+        let mut encoder = png::Encoder::new(open(path)?, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        Ok(Self(encoder.write_header()?.into_stream_writer()?))
+    }
+
+    /// Appends the rows of `rows`, which is as wide as the PNG.
+    pub fn write(&mut self, rows: &image::RgbaImage) -> io::Result<()> {
+        self.0.write_all(rows)
+    }
+
+    /// Fails unless all rows were written.
+    pub fn finish(self) -> io::Result<()> {
+        Ok(self.0.finish()?)
+    }
+}
+
+/// Opens `path` for writing, or stdout for `-`.
+fn open(path: &Path) -> io::Result<Writer> {
+    Ok(BufWriter::new(if path == Path::new("-") {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(path)?)
+    }))
+}
+
+/// Returns `frame_rate` as a fraction, exact to a thousandth of a frame per second.
+fn ratio(frame_rate: f32) -> (u32, u32) {
+    fn gcd(a: u32, b: u32) -> u32 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    let numerator = (frame_rate * 1000.).round() as u32;
+    let divisor = gcd(numerator, 1000);
+
+    (numerator / divisor, 1000 / divisor)
+}
+
+/// Converts `frame` to limited range BT.601 Y, Cb and Cr planes, dropping alpha.
+fn yuv444(frame: &image::RgbaImage) -> Vec<u8> {
+    let len = (frame.width() * frame.height()) as usize;
+    let mut planes = vec![0; 3 * len];
+
+    for (i, pixel) in frame.pixels().enumerate() {
+        let [r, g, b, _] = pixel.0.map(f32::from);
+
+        planes[i] = (16. + 0.257 * r + 0.504 * g + 0.098 * b).round() as u8;
+        planes[len + i] = (128. - 0.148 * r - 0.291 * g + 0.439 * b).round() as u8;
+        planes[2 * len + i] = (128. + 0.439 * r - 0.368 * g - 0.071 * b).round() as u8;
+    }
+
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Width whose rows need padding to the 256 bytes `bytes_per_row` is aligned to.
+    const WIDTH: u32 = 1000;
+    const HEIGHT: u32 = 2;
+
+    /// Lays out `pixel(x, y)` in rows `bytes_per_row` apart, filling the padding with garbage.
+    fn rows<const N: usize>(bytes_per_row: usize, pixel: impl Fn(u32, u32) -> [u8; N]) -> Vec<u8> {
+        (0..HEIGHT)
+            .flat_map(|y| {
+                let mut row: Vec<u8> = (0..WIDTH).flat_map(|x| pixel(x, y)).collect();
+                row.resize(bytes_per_row, 0xaa);
+                row
+            })
+            .collect()
+    }
+
+    fn rgba8(x: u32, y: u32) -> [u8; 4] {
+        [x as u8, (x >> 8) as u8, y as u8, 255]
+    }
+
+    fn read(data: &[u8], bytes_per_row: usize, format: wgpu::TextureFormat) -> Frame {
+        Frame::from_bytes(data, bytes_per_row, WIDTH, HEIGHT, format).unwrap()
+    }
+
+    #[test]
+    fn from_bytes_rgba8() {
+        let data = rows(4096, rgba8);
+
+        let Frame::Rgba8(frame) = read(&data, 4096, wgpu::TextureFormat::Rgba8Unorm) else {
+            panic!("expected 8 bit frame");
+        };
+
+        assert_eq!(frame.dimensions(), (WIDTH, HEIGHT));
+        assert!(frame
+            .enumerate_pixels()
+            .all(|(x, y, pixel)| pixel.0 == rgba8(x, y)));
+    }
+
+    #[test]
+    fn from_bytes_bgra8() {
+        let data = rows(4096, |x, y| {
+            let [r, g, b, a] = rgba8(x, y);
+            [b, g, r, a]
+        });
+
+        let Frame::Rgba8(frame) = read(&data, 4096, wgpu::TextureFormat::Bgra8Unorm) else {
+            panic!("expected 8 bit frame");
+        };
+
+        assert!(frame
+            .enumerate_pixels()
+            .all(|(x, y, pixel)| pixel.0 == rgba8(x, y)));
+    }
+
+    #[test]
+    fn from_bytes_rgba16_float() {
+        // 1, -2, 0.5 and 0 as half floats.
+        let halves = [0x3c00u16, 0xc000, 0x3800, 0x0000];
+        let data = rows(8192, |_, _| {
+            bytemuck::cast::<_, [u8; 8]>(halves.map(u16::to_ne_bytes))
+        });
+
+        let Frame::Rgba32F(frame) = read(&data, 8192, wgpu::TextureFormat::Rgba16Float) else {
+            panic!("expected float frame");
+        };
+
+        assert_eq!(frame.dimensions(), (WIDTH, HEIGHT));
+        assert!(frame.pixels().all(|pixel| pixel.0 == [1., -2., 0.5, 0.]));
+    }
+
+    #[test]
+    fn from_bytes_rgba32_float() {
+        let data = rows(16128, |x, y| {
+            bytemuck::cast::<_, [u8; 16]>([x as f32, y as f32, 0., 1.].map(f32::to_ne_bytes))
+        });
+
+        let Frame::Rgba32F(frame) = read(&data, 16128, wgpu::TextureFormat::Rgba32Float) else {
+            panic!("expected float frame");
+        };
+
+        assert!(frame
+            .enumerate_pixels()
+            .all(|(x, y, pixel)| pixel.0 == [x as f32, y as f32, 0., 1.]));
+    }
+
+    #[test]
+    fn from_bytes_rejects_other_formats() {
+        let data = rows(4096, rgba8);
+
+        for format in [
+            wgpu::TextureFormat::Rgb10a2Unorm,
+            wgpu::TextureFormat::R8Unorm,
+            wgpu::TextureFormat::Depth24Plus,
+        ] {
+            assert!(Frame::from_bytes(&data, 4096, WIDTH, HEIGHT, format).is_err());
+        }
+    }
+
+    #[test]
+    fn f16_to_f32_converts() {
+        assert_eq!(f16_to_f32(0x0000), 0.);
+        assert_eq!(f16_to_f32(0x3c00), 1.);
+        assert_eq!(f16_to_f32(0xc000), -2.);
+        assert_eq!(f16_to_f32(0x7bff), 65504.);
+        // Smallest subnormal.
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xfc00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn ratio_is_exact() {
+        assert_eq!(ratio(30.), (30, 1));
+        assert_eq!(ratio(0.5), (1, 2));
+        assert_eq!(ratio(29.97), (2997, 100));
+        assert_eq!(ratio(23.976), (2997, 125));
+    }
+
+    #[test]
+    fn yuv444_planes() {
+        let frame =
+            image::RgbaImage::from_raw(2, 1, vec![255, 255, 255, 255, 255, 0, 0, 0]).unwrap();
+
+        // White, then red, in the Y, Cb and Cr planes.
+        assert_eq!(yuv444(&frame), [235, 82, 128, 90, 128, 240]);
+    }
+}
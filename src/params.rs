@@ -0,0 +1,314 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::shader::WatchedFile;
+
+/// The type of a parameter, which decides its WGSL type and where it lands in the buffer.
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    Float,
+    Int,
+    Vec2,
+    Vec3,
+    Vec4,
+    /// A `vec4<f32>` whose default may leave out the alpha, which is then 1.
+    Color,
+}
+
+impl ParamType {
+    fn components(self) -> usize {
+        match self {
+            ParamType::Float | ParamType::Int => 1,
+            ParamType::Vec2 => 2,
+            ParamType::Vec3 => 3,
+            ParamType::Vec4 | ParamType::Color => 4,
+        }
+    }
+
+    /// Alignment in a WGSL uniform buffer, which is also the size except for `vec3`.
+    fn align(self) -> usize {
+        match self {
+            ParamType::Float | ParamType::Int => 4,
+            ParamType::Vec2 => 8,
+            ParamType::Vec3 | ParamType::Vec4 | ParamType::Color => 16,
+        }
+    }
+
+    fn wgsl(self) -> &'static str {
+        match self {
+            ParamType::Float => "f32",
+            ParamType::Int => "i32",
+            ParamType::Vec2 => "vec2<f32>",
+            ParamType::Vec3 => "vec3<f32>",
+            ParamType::Vec4 | ParamType::Color => "vec4<f32>",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+enum Value {
+    Scalar(f32),
+    Vector(Vec<f32>),
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Param {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub ty: ParamType,
+
+    /// Zero when left out.
+    default: Option<Value>,
+}
+
+impl Param {
+    /// Returns the default value with one entry per component.
+    fn value(&self) -> Result<Vec<f32>, String> {
+        let mut value = match &self.default {
+            None => vec![0.; self.ty.components()],
+            Some(Value::Scalar(value)) => vec![*value],
+            Some(Value::Vector(value)) => value.clone(),
+        };
+
+        if self.ty == ParamType::Color && value.len() == 3 {
+            value.push(1.);
+        }
+
+        if value.len() != self.ty.components() {
+            return Err(format!(
+                "`{}` is a {:?} but its default has {} components",
+                self.name,
+                self.ty,
+                value.len()
+            ));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Parameters declared in a TOML file as an array of `param` tables, e.g.
+///
+/// ```toml
+/// [[param]]
+/// name = "speed"
+/// type = "float"
+/// default = 1.5
+///
+/// [[param]]
+/// name = "tint"
+/// type = "color"
+/// default = [1.0, 0.5, 0.2]
+/// ```
+///
+/// They are bound at group 0, binding 1, next to [`Uniform`](crate::Uniform), as a struct with
+/// one field per parameter in the order they are declared, see [`Params::wgsl`].
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Params {
+    #[serde(default, rename = "param")]
+    pub params: Vec<Param>,
+}
+
+impl Params {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let params: Self = toml::from_str(source).map_err(|err| err.to_string())?;
+
+        for param in &params.params {
+            param.value()?;
+        }
+
+        Ok(params)
+    }
+
+    /// Packs the defaults following WGSL's uniform buffer layout: every field is aligned to its
+    /// type's alignment and the struct's size rounds up to 16 bytes. An empty struct still takes
+    /// 16 bytes, since bindings cannot be empty.
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for param in &self.params {
+            bytes.resize(bytes.len().next_multiple_of(param.ty.align()), 0);
+
+            for component in param.value().unwrap() {
+                match param.ty {
+                    ParamType::Int => bytes.extend((component as i32).to_ne_bytes()),
+                    _ => bytes.extend(component.to_ne_bytes()),
+                }
+            }
+        }
+
+        bytes.resize(bytes.len().next_multiple_of(16).max(16), 0);
+
+        bytes
+    }
+
+    /// Returns the WGSL struct shaders declare to read the parameters.
+    pub fn wgsl(&self) -> String {
+        let mut wgsl = "struct Params {\n".to_owned();
+
+        for param in &self.params {
+            wgsl += &format!("    {}: {},\n", param.name, param.ty.wgsl());
+        }
+
+        wgsl + "};"
+    }
+}
+
+/// Returns how many bytes the shader `module` reads from the parameters bound at group 0,
+/// binding 1, 0 if it reads none.
+pub(crate) fn params_size(module: &wgpu::naga::Module) -> u64 {
+    use wgpu::naga;
+
+    let binding = Some(naga::ResourceBinding {
+        group: 0,
+        binding: 1,
+    });
+
+    let size = module
+        .global_variables
+        .iter()
+        .find(|(_, variable)| variable.binding == binding)
+        .map_or(0, |(_, variable)| {
+            module.types[variable.ty].inner.size(module.to_ctx())
+        });
+
+    size as u64
+}
+
+/// A parameter file loaded from disk, reloaded once it changes like [`ShaderFile`].
+///
+/// [`ShaderFile`]: crate::ShaderFile
+pub struct ParamsFile {
+    file: WatchedFile,
+}
+
+impl ParamsFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            file: WatchedFile::new(path.into()),
+        }
+    }
+
+    /// Returns the file next to the shader at `path`, named like it with a `.toml` extension.
+    pub fn sidecar(path: &Path) -> Self {
+        Self::new(path.with_extension("toml"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path().is_file()
+    }
+
+    pub fn load(&self) -> io::Result<Params> {
+        let source = std::fs::read_to_string(self.path())?;
+
+        Params::parse(&source).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Returns `true` once each time the file's modification time changes, see
+    /// [`ShaderFile::changed`](crate::ShaderFile::changed).
+    pub fn changed(&mut self) -> bool {
+        self.file.changed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks(4)
+            .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn bytes_follow_uniform_layout() {
+        let params = Params::parse(
+            r#"
+            [[param]]
+            name = "a"
+            type = "float"
+            default = 1.0
+
+            [[param]]
+            name = "b"
+            type = "vec3"
+            default = [2.0, 3.0, 4.0]
+
+            [[param]]
+            name = "c"
+            type = "float"
+            default = 5.0
+
+            [[param]]
+            name = "d"
+            type = "vec2"
+            default = [6.0, 7.0]
+
+            [[param]]
+            name = "e"
+            type = "int"
+            default = 8
+
+            [[param]]
+            name = "f"
+            type = "color"
+            default = [0.25, 0.5, 0.75]
+            "#,
+        )
+        .unwrap();
+
+        let bytes = params.bytes();
+
+        assert_eq!(bytes.len(), 64);
+        // `b` is aligned to 16 bytes, and `c` packs into the 4 bytes left after it.
+        assert_eq!(floats(&bytes[..32]), [1., 0., 0., 0., 2., 3., 4., 5.]);
+        assert_eq!(floats(&bytes[32..40]), [6., 7.]);
+        assert_eq!(i32::from_ne_bytes(bytes[40..44].try_into().unwrap()), 8);
+        assert_eq!(floats(&bytes[48..]), [0.25, 0.5, 0.75, 1.]);
+    }
+
+    #[test]
+    fn bytes_round_up_to_16() {
+        assert_eq!(Params::default().bytes(), [0; 16]);
+
+        let params = Params::parse("[[param]]\nname = \"a\"\ntype = \"vec2\"\n").unwrap();
+
+        assert_eq!(params.bytes(), [0; 16]);
+    }
+
+    #[test]
+    fn params_size_matches_bytes() {
+        let params = Params::parse(
+            "[[param]]\nname = \"a\"\ntype = \"float\"\n\n\
+             [[param]]\nname = \"b\"\ntype = \"vec3\"\n",
+        )
+        .unwrap();
+
+        let source = format!(
+            "{}\n@group(0) @binding(1) var<uniform> params: Params;\n\
+             @fragment fn fs_main() -> @location(0) vec4<f32> {{\n\
+             return vec4(params.b, params.a);\n\
+             }}",
+            params.wgsl()
+        );
+
+        let module = wgpu::naga::front::wgsl::parse_str(&source).unwrap();
+        let empty = wgpu::naga::Module::default();
+
+        assert_eq!(params_size(&module), params.bytes().len() as u64);
+        assert_eq!(params_size(&empty), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Frames the reported minimum, average and maximum are taken over.
+const SAMPLES: usize = 120;
+/// How often timings are reported.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Durations of the last frames, in milliseconds.
+#[derive(Default)]
+struct Samples(VecDeque<f32>);
+
+impl Samples {
+    fn push(&mut self, duration: Duration) {
+        if self.0.len() == SAMPLES {
+            self.0.pop_front();
+        }
+
+        self.0.push_back(1000. * duration.as_secs_f32());
+    }
+
+    fn average(&self) -> Option<f32> {
+        (!self.0.is_empty()).then(|| self.0.iter().sum::<f32>() / self.0.len() as f32)
+    }
+
+    /// Formats the average followed by the minimum and maximum.
+    fn summary(&self, name: &str) -> Option<String> {
+        let average = self.average()?;
+        let min = self.0.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = self.0.iter().copied().fold(0., f32::max);
+
+        Some(format!(
+            "{} {:.2} ms ({:.2}-{:.2})",
+            name, average, min, max
+        ))
+    }
+}
+
+/// Timestamps written before and after a frame's passes, read back once the GPU gets to them
+/// instead of waiting for it.
+struct Timestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    /// Nanoseconds per tick.
+    period: f32,
+
+    /// Set while `read_buffer` is being mapped, which frames skip writing timestamps during.
+    mapped: Option<flume::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    /// Whether the current frame writes timestamps.
+    writing: bool,
+}
+
+/// Measures how long frames take on the CPU, and on the GPU if the device supports timestamp
+/// queries.
+pub(crate) struct Profiler {
+    timestamps: Option<Timestamps>,
+
+    started: Option<Instant>,
+    reported: Instant,
+
+    interval: Samples,
+    cpu: Samples,
+    gpu: Samples,
+}
+
+impl Profiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let timestamps = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| Timestamps {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("Profiler Timestamps"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: 2,
+                }),
+                resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Resolve Buffer"),
+                    size: 2 * wgpu::QUERY_SIZE as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Read Buffer"),
+                    size: 2 * wgpu::QUERY_SIZE as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                period: queue.get_timestamp_period(),
+
+                mapped: None,
+                writing: false,
+            });
+
+        if timestamps.is_none() {
+            log::info!("Timestamp queries are unsupported, only timing frames on the CPU");
+        }
+
+        Self {
+            timestamps,
+
+            started: None,
+            reported: Instant::now(),
+
+            interval: Samples::default(),
+            cpu: Samples::default(),
+            gpu: Samples::default(),
+        }
+    }
+
+    /// Starts timing a frame, before anything is recorded into `encoder`.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let now = Instant::now();
+
+        if let Some(started) = self.started {
+            self.interval.push(now - started);
+        }
+        self.started = Some(now);
+
+        if let Some(timestamps) = &mut self.timestamps {
+            timestamps.writing = timestamps.mapped.is_none();
+
+            if timestamps.writing {
+                encoder.write_timestamp(&timestamps.query_set, 0);
+            }
+        }
+    }
+
+    /// Stops timing the frame, after everything is recorded into `encoder`.
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(timestamps) = &self.timestamps else {
+            return;
+        };
+
+        if timestamps.writing {
+            encoder.write_timestamp(&timestamps.query_set, 1);
+            encoder.resolve_query_set(&timestamps.query_set, 0..2, &timestamps.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &timestamps.resolve_buffer,
+                0,
+                &timestamps.read_buffer,
+                0,
+                timestamps.read_buffer.size(),
+            );
+        }
+    }
+
+    /// Finishes the frame once submitted, returning a report of the last frames' timings about
+    /// once a second.
+    pub fn submitted(&mut self, device: &wgpu::Device) -> Option<String> {
+        if let Some(started) = self.started {
+            self.cpu.push(started.elapsed());
+        }
+
+        if let Some(timestamps) = &mut self.timestamps {
+            if timestamps.writing {
+                let (sender, mapped) = flume::bounded(1);
+
+                timestamps
+                    .read_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        let _ = sender.send(result);
+                    });
+
+                timestamps.mapped = Some(mapped);
+            }
+
+            device.poll(wgpu::Maintain::Poll);
+
+            let received = timestamps
+                .mapped
+                .as_ref()
+                .and_then(|mapped| mapped.try_recv().ok());
+
+            if let Some(result) = received {
+                timestamps.mapped = None;
+
+                if result.is_ok() {
+                    let data = timestamps.read_buffer.slice(..).get_mapped_range();
+                    let [begin, end]: [u64; 2] = bytemuck::pod_read_unaligned(&data);
+                    drop(data);
+
+                    timestamps.read_buffer.unmap();
+
+                    if end > begin {
+                        let nanos = (end - begin) as f64 * timestamps.period as f64;
+
+                        self.gpu.push(Duration::from_nanos(nanos as u64));
+                    }
+                }
+            }
+        }
+
+        if self.reported.elapsed() < REPORT_INTERVAL {
+            return None;
+        }
+        self.reported = Instant::now();
+
+        let frame_rate = self
+            .interval
+            .average()
+            .map(|interval| format!("{:.0} fps", 1000. / interval));
+
+        let report = [frame_rate, self.cpu.summary("CPU"), self.gpu.summary("GPU")];
+
+        Some(report.into_iter().flatten().collect::<Vec<_>>().join(", "))
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The language a fragment shader file is written in.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    Wgsl,
+    /// A GLSL fragment shader with its own `main`
+    Glsl,
+    /// GLSL defining Shadertoy's `mainImage`
+    Shadertoy,
+}
+
+impl Language {
+    /// Guesses the language of `source` read from `path`: `.frag` and `.glsl` files are GLSL,
+    /// unless they define `mainImage` for Shadertoy, and anything else is WGSL.
+    pub fn detect(path: &Path, source: &str) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("frag" | "glsl") if source.contains("mainImage") => Self::Shadertoy,
+            Some("frag" | "glsl") => Self::Glsl,
+            _ => Self::Wgsl,
+        }
+    }
+}
+
+/// Builds a GLSL fragment shader with a `main` entry point.
+///
+/// The uniforms from `create_uniforms` are read through a block at set 0, binding 0 whose members
+/// follow [`crate::Uniform`], e.g.
+///
+/// ```glsl
+/// layout(set = 0, binding = 0) uniform Uniforms {
+///     vec2 resolution;
+///     float time;
+/// };
+/// ```
+pub fn glsl(source: &str) -> wgpu::ShaderModuleDescriptor<'static> {
+    wgpu::ShaderModuleDescriptor {
+        label: Some("GLSL Shader"),
+        source: wgpu::ShaderSource::Glsl {
+            shader: Cow::Owned(source.to_owned()),
+            stage: wgpu::naga::ShaderStage::Fragment,
+            defines: Default::default(),
+        },
+    }
+}
+
+/// Returns the entry point of a fragment shader built from `source`: `main` for GLSL and
+/// `fs_main` for WGSL.
+pub fn fragment_entry_point(source: &wgpu::ShaderSource) -> &'static str {
+    match source {
+        wgpu::ShaderSource::Glsl { .. } => "main",
+        _ => "fs_main",
+    }
+}
+
+/// Parses the fragment shader in `desc` ahead of wgpu, returning the descriptor to create it from
+/// along with the parsed module, or the errors to log.
+///
+/// wgpu reports errors in WGSL through error scopes but panics on GLSL that fails to parse, so
+/// GLSL is handed to it already parsed. Take the entry point from `desc` beforehand.
+pub(crate) fn parse(
+    desc: wgpu::ShaderModuleDescriptor<'_>,
+) -> Result<(wgpu::ShaderModuleDescriptor<'_>, wgpu::naga::Module), String> {
+    use wgpu::naga;
+
+    let module = match &desc.source {
+        wgpu::ShaderSource::Wgsl(source) => {
+            naga::front::wgsl::parse_str(source).map_err(|err| err.emit_to_string(source))?
+        }
+        wgpu::ShaderSource::Glsl {
+            shader,
+            stage,
+            defines,
+        } => {
+            let options = naga::front::glsl::Options {
+                stage: *stage,
+                defines: defines.clone(),
+            };
+
+            let module = naga::front::glsl::Frontend::default()
+                .parse(&options, shader)
+                .map_err(|errors| {
+                    errors
+                        .iter()
+                        .map(|err| {
+                            let location = err.meta.location(shader);
+
+                            format!(
+                                "{}:{}: {}",
+                                location.line_number, location.line_position, err
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })?;
+
+            let desc = wgpu::ShaderModuleDescriptor {
+                label: desc.label,
+                source: wgpu::ShaderSource::Naga(Cow::Owned(module.clone())),
+            };
+
+            return Ok((desc, module));
+        }
+        wgpu::ShaderSource::Naga(module) => module.as_ref().clone(),
+        _ => return Err("only WGSL and GLSL shaders are supported".to_owned()),
+    };
+
+    Ok((desc, module))
+}
+
+/// A fragment shader loaded from disk, remembering when it was last modified so it can be
+/// reloaded once the file changes.
+pub struct ShaderFile {
+    file: WatchedFile,
+    language: Option<Language>,
+    buffer: bool,
+}
+
+impl ShaderFile {
+    /// Watches the shader at `path`, detecting its language on every load unless `language` is
+    /// given.
+    pub fn new(path: impl Into<PathBuf>, language: Option<Language>) -> Self {
+        Self {
+            file: WatchedFile::new(path.into()),
+            language,
+            buffer: false,
+        }
+    }
+
+    /// Marks the shader as a buffer pass rather than the final image.
+    pub fn buffer(mut self) -> Self {
+        self.buffer = true;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    pub fn load(&self) -> std::io::Result<wgpu::ShaderModuleDescriptor<'static>> {
+        let source = std::fs::read_to_string(self.path())?;
+
+        let language = self
+            .language
+            .unwrap_or_else(|| Language::detect(self.path(), &source));
+
+        Ok(match language {
+            Language::Wgsl => wgpu::ShaderModuleDescriptor {
+                label: Some("Fragment Shader"),
+                source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+            },
+            Language::Glsl => glsl(&source),
+            Language::Shadertoy if self.buffer => crate::shadertoy_buffer(&source),
+            Language::Shadertoy => crate::shadertoy(&source),
+        })
+    }
+
+    /// Returns `true` once each time the file's modification time changes.
+    ///
+    /// A file that is missing, e.g. while an editor replaces it, is not reported as changed.
+    pub fn changed(&mut self) -> bool {
+        self.file.changed()
+    }
+}
+
+/// A path and the modification time it had when last checked.
+pub(crate) struct WatchedFile {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+
+        Self { path, modified }
+    }
+
+    /// See [`ShaderFile::changed`].
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hands_shadertoy_glsl_over_parsed() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n\
+                      fragColor = vec4(fragCoord / iResolution.xy, 0.5 + 0.5 * sin(iTime), 1.0);\n\
+                      }\n";
+
+        let (desc, module) = parse(crate::shadertoy(source)).unwrap();
+
+        assert!(matches!(desc.source, wgpu::ShaderSource::Naga(_)));
+        assert!(module.entry_points.iter().any(|entry| entry.name == "main"));
+    }
+
+    #[test]
+    fn parse_reports_shadertoy_errors() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n\
+                      fragColor = vec4(1.0\n\
+                      }\n";
+
+        assert!(parse(crate::shadertoy(source)).is_err());
+    }
+
+    #[test]
+    fn parse_hands_plain_glsl_over_parsed() {
+        let source = include_str!("../examples/tutorial-glsl/shader.frag");
+
+        let (desc, _) = parse(glsl(source)).unwrap();
+
+        assert!(matches!(desc.source, wgpu::ShaderSource::Naga(_)));
+    }
+
+    #[test]
+    fn parse_reports_glsl_errors_with_lines() {
+        let path = std::env::temp_dir().join("shader-rs-parse-test.frag");
+        std::fs::write(
+            &path,
+            "#version 450\n\
+             layout(location = 0) out vec4 color;\n\
+             void main() { color = vec4(1.0 }\n",
+        )
+        .unwrap();
+
+        let desc = ShaderFile::new(&path, None).load().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let err = parse(desc).unwrap_err();
+
+        assert!(err.starts_with("3:"), "{}", err);
+    }
+
+    #[test]
+    fn parse_keeps_wgsl_source() {
+        let source = include_str!("../examples/tutorial/shader.wgsl");
+
+        let (desc, _) = parse(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+        .unwrap();
+
+        assert!(matches!(desc.source, wgpu::ShaderSource::Wgsl(_)));
+
+        assert!(parse(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl("fn fs_main( {}".into()),
+        })
+        .is_err());
+    }
+}
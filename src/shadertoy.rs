@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+
+/// Declares the Shadertoy inputs on top of the `Uniform` block built by `create_uniforms`.
+///
+/// `Uniform` uses a top-left origin like `@builtin(position)`, so coordinates are flipped here
+/// to match Shadertoy's bottom-left origin. Buffers are written without flipping, so that their
+/// rows are stored bottom-up and `texture(iChannel0, fragCoord / iResolution.xy)` reads back the
+/// same pixel.
+const PRELUDE: &str = r#"#version 450
+
+layout(set = 0, binding = 0) uniform Uniforms {
+    vec2 shadertoy_resolution;
+    float shadertoy_time;
+    float shadertoy_time_delta;
+    int shadertoy_frame;
+    float shadertoy_frame_rate;
+    float shadertoy_aspect;
+    float shadertoy_pixel_ratio;
+    vec4 shadertoy_mouse;
+    vec4 shadertoy_date;
+    vec4 shadertoy_channel_resolution[4];
+    vec2 shadertoy_offset;
+};
+
+vec4 shadertoy_flip_mouse() {
+    if (shadertoy_mouse == vec4(0.0)) {
+        return vec4(0.0);
+    }
+
+    float height = shadertoy_resolution.y;
+    float click = sign(shadertoy_mouse.w) * (height - abs(shadertoy_mouse.w));
+
+    return vec4(shadertoy_mouse.x, height - shadertoy_mouse.y, shadertoy_mouse.z, click);
+}
+
+#define iTime shadertoy_time
+#define iTimeDelta shadertoy_time_delta
+#define iFrame shadertoy_frame
+#define iFrameRate shadertoy_frame_rate
+#define iResolution vec3(shadertoy_resolution, 1.0)
+#define iMouse shadertoy_flip_mouse()
+#define iDate shadertoy_date
+
+layout(set = 1, binding = 0) uniform texture2D shadertoy_channel0;
+layout(set = 1, binding = 1) uniform sampler shadertoy_channel0_sampler;
+layout(set = 1, binding = 2) uniform texture2D shadertoy_channel1;
+layout(set = 1, binding = 3) uniform sampler shadertoy_channel1_sampler;
+layout(set = 1, binding = 4) uniform texture2D shadertoy_channel2;
+layout(set = 1, binding = 5) uniform sampler shadertoy_channel2_sampler;
+layout(set = 1, binding = 6) uniform texture2D shadertoy_channel3;
+layout(set = 1, binding = 7) uniform sampler shadertoy_channel3_sampler;
+
+#define iChannel0 sampler2D(shadertoy_channel0, shadertoy_channel0_sampler)
+#define iChannel1 sampler2D(shadertoy_channel1, shadertoy_channel1_sampler)
+#define iChannel2 sampler2D(shadertoy_channel2, shadertoy_channel2_sampler)
+#define iChannel3 sampler2D(shadertoy_channel3, shadertoy_channel3_sampler)
+
+vec3 iChannelResolution[4];
+"#;
+
+const EPILOGUE: &str = r#"
+layout(location = 0) out vec4 shadertoy_frag_color;
+
+void main() {
+    for (int i = 0; i < 4; i++) {
+        iChannelResolution[i] = shadertoy_channel_resolution[i].xyz;
+    }
+
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+    vec2 frag_coord = gl_FragCoord.xy + shadertoy_offset;
+
+#ifdef SHADERTOY_BUFFER
+    mainImage(color, frag_coord);
+
+    shadertoy_frag_color = color;
+#else
+    mainImage(color, vec2(frag_coord.x, shadertoy_resolution.y - frag_coord.y));
+
+    shadertoy_frag_color = vec4(color.rgb, 1.0);
+#endif
+}
+"#;
+
+/// Wraps a Shadertoy image shader, i.e. GLSL defining
+/// `void mainImage(out vec4 fragColor, in vec2 fragCoord)`, into a fragment shader fed by the
+/// uniforms from `create_uniforms`.
+///
+/// The result defines `SHADERTOY`, which also tells [`is_shadertoy`] apart from plain GLSL.
+pub fn shadertoy(source: &str) -> wgpu::ShaderModuleDescriptor<'static> {
+    let mut defines = wgpu::naga::FastHashMap::default();
+    defines.insert("SHADERTOY".to_owned(), "1".to_owned());
+
+    wgpu::ShaderModuleDescriptor {
+        label: Some("Shadertoy Shader"),
+        source: wgpu::ShaderSource::Glsl {
+            shader: Cow::Owned(format!("{}\n{}\n{}", PRELUDE, source, EPILOGUE)),
+            stage: wgpu::naga::ShaderStage::Fragment,
+            defines,
+        },
+    }
+}
+
+/// Returns `true` for shaders built by [`shadertoy`] or [`shadertoy_buffer`].
+pub fn is_shadertoy(source: &wgpu::ShaderSource) -> bool {
+    matches!(source, wgpu::ShaderSource::Glsl { defines, .. } if defines.contains_key("SHADERTOY"))
+}
+
+/// Like [`shadertoy`], but for a Shadertoy buffer pass, which keeps its alpha and is stored
+/// bottom-up.
+pub fn shadertoy_buffer(source: &str) -> wgpu::ShaderModuleDescriptor<'static> {
+    let mut desc = shadertoy(source);
+
+    if let wgpu::ShaderSource::Glsl { defines, .. } = &mut desc.source {
+        defines.insert("SHADERTOY_BUFFER".to_owned(), "1".to_owned());
+    }
+
+    desc
+}